@@ -10,7 +10,12 @@ use {
     },
     solana_zk_token_sdk::{
         zk_token_proof_instruction::*,
-        zk_token_proof_program::id,
+        zk_token_proof_program::{
+            id, VERIFY_CIPHERTEXT_CIPHERTEXT_EQUALITY_COMPUTE_UNITS,
+            VERIFY_PUBKEY_VALIDITY_COMPUTE_UNITS, VERIFY_TRANSFER_COMPUTE_UNITS,
+            VERIFY_TRANSFER_WITH_FEE_COMPUTE_UNITS, VERIFY_WITHDRAW_COMPUTE_UNITS,
+            VERIFY_ZERO_BALANCE_COMPUTE_UNITS,
+        },
         zk_token_proof_state::{ProofContextState, ProofContextStateMeta},
     },
     std::result::Result,
@@ -138,7 +143,7 @@ declare_process_instruction!(process_instruction, 0, |invoke_context| {
         ProofInstruction::VerifyZeroBalance => {
             if native_programs_consume_cu {
                 invoke_context
-                    .consume_checked(6_012)
+                    .consume_checked(VERIFY_ZERO_BALANCE_COMPUTE_UNITS)
                     .map_err(|_| InstructionError::ComputationalBudgetExceeded)?;
             }
             ic_msg!(invoke_context, "VerifyZeroBalance");
@@ -147,7 +152,7 @@ declare_process_instruction!(process_instruction, 0, |invoke_context| {
         ProofInstruction::VerifyWithdraw => {
             if native_programs_consume_cu {
                 invoke_context
-                    .consume_checked(112_454)
+                    .consume_checked(VERIFY_WITHDRAW_COMPUTE_UNITS)
                     .map_err(|_| InstructionError::ComputationalBudgetExceeded)?;
             }
             ic_msg!(invoke_context, "VerifyWithdraw");
@@ -156,7 +161,7 @@ declare_process_instruction!(process_instruction, 0, |invoke_context| {
         ProofInstruction::VerifyCiphertextCiphertextEquality => {
             if native_programs_consume_cu {
                 invoke_context
-                    .consume_checked(7_943)
+                    .consume_checked(VERIFY_CIPHERTEXT_CIPHERTEXT_EQUALITY_COMPUTE_UNITS)
                     .map_err(|_| InstructionError::ComputationalBudgetExceeded)?;
             }
             ic_msg!(invoke_context, "VerifyCiphertextCiphertextEquality");
@@ -168,7 +173,7 @@ declare_process_instruction!(process_instruction, 0, |invoke_context| {
         ProofInstruction::VerifyTransfer => {
             if native_programs_consume_cu {
                 invoke_context
-                    .consume_checked(219_290)
+                    .consume_checked(VERIFY_TRANSFER_COMPUTE_UNITS)
                     .map_err(|_| InstructionError::ComputationalBudgetExceeded)?;
             }
             ic_msg!(invoke_context, "VerifyTransfer");
@@ -177,7 +182,7 @@ declare_process_instruction!(process_instruction, 0, |invoke_context| {
         ProofInstruction::VerifyTransferWithFee => {
             if native_programs_consume_cu {
                 invoke_context
-                    .consume_checked(407_121)
+                    .consume_checked(VERIFY_TRANSFER_WITH_FEE_COMPUTE_UNITS)
                     .map_err(|_| InstructionError::ComputationalBudgetExceeded)?;
             }
             ic_msg!(invoke_context, "VerifyTransferWithFee");
@@ -186,7 +191,7 @@ declare_process_instruction!(process_instruction, 0, |invoke_context| {
         ProofInstruction::VerifyPubkeyValidity => {
             if native_programs_consume_cu {
                 invoke_context
-                    .consume_checked(2_619)
+                    .consume_checked(VERIFY_PUBKEY_VALIDITY_COMPUTE_UNITS)
                     .map_err(|_| InstructionError::ComputationalBudgetExceeded)?;
             }
             ic_msg!(invoke_context, "VerifyPubkeyValidity");
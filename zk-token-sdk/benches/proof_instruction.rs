@@ -0,0 +1,60 @@
+#![feature(test)]
+
+extern crate test;
+use {
+    solana_zk_token_sdk::zk_token_proof_instruction::{test_utils::*, *},
+    test::Bencher,
+};
+
+#[bench]
+fn bench_encode_verify_zero_balance(bencher: &mut Bencher) {
+    let proof_data = zero_balance_proof_data();
+    bencher.iter(|| verify_zero_balance(None, &proof_data));
+}
+
+#[bench]
+fn bench_encode_verify_withdraw(bencher: &mut Bencher) {
+    let proof_data = withdraw_proof_data();
+    bencher.iter(|| verify_withdraw(None, &proof_data));
+}
+
+#[bench]
+fn bench_encode_verify_ciphertext_ciphertext_equality(bencher: &mut Bencher) {
+    let proof_data = ciphertext_ciphertext_equality_proof_data();
+    bencher.iter(|| verify_ciphertext_ciphertext_equality(None, &proof_data));
+}
+
+#[bench]
+fn bench_encode_verify_transfer(bencher: &mut Bencher) {
+    let proof_data = transfer_proof_data();
+    bencher.iter(|| verify_transfer(None, &proof_data));
+}
+
+#[bench]
+fn bench_encode_verify_transfer_with_fee(bencher: &mut Bencher) {
+    let proof_data = transfer_with_fee_proof_data();
+    bencher.iter(|| verify_transfer_with_fee(None, &proof_data));
+}
+
+#[bench]
+fn bench_encode_verify_pubkey_validity(bencher: &mut Bencher) {
+    let proof_data = pubkey_validity_proof_data();
+    bencher.iter(|| verify_pubkey_validity(None, &proof_data));
+}
+
+#[bench]
+fn bench_instruction_type(bencher: &mut Bencher) {
+    let instruction = verify_transfer(None, &transfer_proof_data());
+    bencher.iter(|| ProofInstruction::instruction_type(test::black_box(&instruction.data)));
+}
+
+#[bench]
+fn bench_proof_data_decode(bencher: &mut Bencher) {
+    let instruction = verify_transfer_with_fee(None, &transfer_with_fee_proof_data());
+    bencher.iter(|| {
+        ProofInstruction::proof_data::<TransferWithFeeData, TransferWithFeeProofContext>(
+            test::black_box(&instruction.data),
+        )
+        .unwrap();
+    });
+}
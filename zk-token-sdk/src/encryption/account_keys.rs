@@ -0,0 +1,67 @@
+//! The encryption keys of a confidential token account.
+
+use {
+    crate::encryption::{auth_encryption::AeKey, elgamal::ElGamalKeypair},
+    solana_sdk::{
+        pubkey::Pubkey,
+        signer::{Signer, SignerError},
+    },
+};
+
+/// The encryption keys of a confidential token account.
+pub struct ConfidentialAccountKeys {
+    /// The ElGamal keypair for the encrypted balances of the account
+    pub elgamal_keypair: ElGamalKeypair,
+    /// The authenticated encryption key for the decryptable balance of the account
+    pub ae_key: AeKey,
+}
+impl ConfidentialAccountKeys {
+    /// Derive the ElGamal keypair and the authenticated encryption key of an account from a
+    /// signer.
+    ///
+    /// The two keys are derived from signatures of messages with distinct labels, so they are
+    /// independent of each other. The keys are identical to those of `ElGamalKeypair::new` and
+    /// `AeKey::new`.
+    pub fn derive(signer: &dyn Signer, address: &Pubkey) -> Result<Self, SignerError> {
+        Ok(Self {
+            elgamal_keypair: ElGamalKeypair::new(signer, address)?,
+            ae_key: AeKey::new(signer, address)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{signature::Keypair, signer::null_signer::NullSigner},
+    };
+
+    #[test]
+    fn test_confidential_account_keys_derive() {
+        let keypair = Keypair::new();
+        let address = Pubkey::new_unique();
+
+        let keys = ConfidentialAccountKeys::derive(&keypair, &address).unwrap();
+        assert_ne!(
+            &keys.elgamal_keypair.secret.as_bytes()[..16],
+            &keys.ae_key.0[..]
+        );
+
+        let same_keys = ConfidentialAccountKeys::derive(&keypair, &address).unwrap();
+        assert_eq!(same_keys.ae_key.0, keys.ae_key.0);
+        assert_eq!(
+            same_keys.elgamal_keypair.to_bytes(),
+            keys.elgamal_keypair.to_bytes()
+        );
+        assert_eq!(
+            keys.elgamal_keypair.to_bytes(),
+            ElGamalKeypair::new(&keypair, &address).unwrap().to_bytes()
+        );
+
+        let other_keys = ConfidentialAccountKeys::derive(&keypair, &Pubkey::new_unique()).unwrap();
+        assert_ne!(other_keys.ae_key.0, keys.ae_key.0);
+
+        assert!(ConfidentialAccountKeys::derive(&NullSigner::new(&address), &address).is_err());
+    }
+}
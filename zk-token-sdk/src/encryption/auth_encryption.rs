@@ -4,7 +4,7 @@
 #[cfg(not(target_os = "solana"))]
 use {
     aes_gcm_siv::{
-        aead::{Aead, NewAead},
+        aead::{Aead, NewAead, Payload},
         Aes128GcmSiv,
     },
     rand::{rngs::OsRng, CryptoRng, Rng, RngCore},
@@ -12,8 +12,13 @@ use {
 };
 use {
     arrayref::{array_ref, array_refs},
-    base64::{prelude::BASE64_STANDARD, Engine},
-    sha3::{Digest, Sha3_512},
+    base64::{
+        prelude::{BASE64_STANDARD, BASE64_URL_SAFE_NO_PAD},
+        Engine,
+    },
+    hmac::{Hmac, Mac},
+    serde::{Deserialize, Serialize},
+    sha3::{Digest, Sha3_256, Sha3_512},
     solana_sdk::{
         derivation_path::DerivationPath,
         instruction::Instruction,
@@ -29,8 +34,9 @@ use {
         convert::TryInto,
         error, fmt,
         io::{Read, Write},
+        sync::Arc,
     },
-    subtle::ConstantTimeEq,
+    subtle::{Choice, ConditionallySelectable, ConstantTimeEq},
     zeroize::Zeroize,
 };
 
@@ -41,9 +47,36 @@ pub enum AuthenticatedEncryptionError {
 
     #[error("pubkey does not exist")]
     PubkeyDoesNotExist,
+
+    #[error("ciphertext data length is not a multiple of the ciphertext length")]
+    InvalidCiphertextDataLength,
+
+    #[error("default signature is not suitable for use as key material")]
+    DefaultSignature,
+}
+
+/// The reason that a ciphertext fails to decrypt.
+///
+/// The authenticated encryption scheme cannot distinguish a ciphertext that is decrypted with the
+/// wrong key from a tampered ciphertext, so both are reported as `AuthenticationFailed`.
+#[derive(Error, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DecryptError {
+    #[error("plaintext is not an encoded amount")]
+    MalformedCiphertext,
+
+    #[error("ciphertext failed to authenticate")]
+    AuthenticationFailed,
 }
 
-struct AuthenticatedEncryption;
+/// The marker that precedes the key bytes in the format of `AeKey::write_as_solana_format`.
+pub const AE_KEY_FILE_MARKER: &[u8; 16] = b"solana-ae-key-v1";
+
+const AE_KEY_ARMOR_BEGIN: &str = "-----BEGIN AE KEY-----";
+const AE_KEY_ARMOR_END: &str = "-----END AE KEY-----";
+
+type HmacSha3_256 = Hmac<sha3_010::Sha3_256>;
+
+pub(crate) struct AuthenticatedEncryption;
 impl AuthenticatedEncryption {
     #[cfg(not(target_os = "solana"))]
     fn keygen<T: RngCore + CryptoRng>(rng: &mut T) -> AeKey {
@@ -52,14 +85,28 @@ impl AuthenticatedEncryption {
 
     #[cfg(not(target_os = "solana"))]
     fn encrypt(key: &AeKey, balance: u64) -> AeCiphertext {
-        let mut plaintext = balance.to_le_bytes();
         let nonce: Nonce = OsRng.gen::<[u8; 12]>();
+        Self::encrypt_with_nonce(key, balance, nonce)
+    }
 
-        // The balance and the nonce have fixed length and therefore, encryption should not fail.
-        let ciphertext = Aes128GcmSiv::new(&key.0.into())
-            .encrypt(&nonce.into(), plaintext.as_ref())
-            .expect("authenticated encryption");
+    #[cfg(not(target_os = "solana"))]
+    pub(crate) fn encrypt_with_nonce(key: &AeKey, balance: u64, nonce: Nonce) -> AeCiphertext {
+        Self::encrypt_with_nonce_and_aad(key, balance, nonce, &[])
+    }
 
+    /// Encrypt a balance with associated data `aad`.
+    ///
+    /// Encrypting with an empty associated data is identical to encrypting without associated
+    /// data.
+    #[cfg(not(target_os = "solana"))]
+    fn encrypt_with_nonce_and_aad(
+        key: &AeKey,
+        balance: u64,
+        nonce: Nonce,
+        aad: &[u8],
+    ) -> AeCiphertext {
+        let mut plaintext = balance.to_le_bytes();
+        let ciphertext = Self::encrypt_bytes(key, &nonce, &plaintext, aad);
         plaintext.zeroize();
 
         AeCiphertext {
@@ -68,22 +115,141 @@ impl AuthenticatedEncryption {
         }
     }
 
+    /// Encrypt a plaintext with associated data `aad`.
+    ///
+    /// The caller is responsible for zeroizing the plaintext.
     #[cfg(not(target_os = "solana"))]
-    fn decrypt(key: &AeKey, ct: &AeCiphertext) -> Option<u64> {
-        let plaintext =
-            Aes128GcmSiv::new(&key.0.into()).decrypt(&ct.nonce.into(), ct.ciphertext.as_ref());
+    fn encrypt_bytes(key: &AeKey, nonce: &Nonce, plaintext: &[u8], aad: &[u8]) -> Vec<u8> {
+        // The plaintexts of this module and the nonce have fixed length and therefore, encryption
+        // should not fail.
+        Aes128GcmSiv::new(&key.0.into())
+            .encrypt(
+                &(*nonce).into(),
+                Payload {
+                    msg: plaintext,
+                    aad,
+                },
+            )
+            .expect("authenticated encryption")
+    }
 
-        if let Ok(plaintext) = plaintext {
-            let amount_bytes: [u8; 8] = plaintext.try_into().unwrap();
-            Some(u64::from_le_bytes(amount_bytes))
-        } else {
-            None
+    /// Decrypt a ciphertext with associated data `aad`, returning the plaintext.
+    #[cfg(not(target_os = "solana"))]
+    fn decrypt_bytes(
+        key: &AeKey,
+        nonce: &Nonce,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, DecryptError> {
+        Self::decrypt_bytes_with_cipher(&Aes128GcmSiv::new(&key.0.into()), nonce, ciphertext, aad)
+    }
+
+    /// Decrypt a ciphertext with associated data `aad` under a cipher instance of the key.
+    ///
+    /// This allows a cipher instance to be reused across many decryptions. All decryptions of the
+    /// module go through this function, so with the `tracing` feature, each decryption is wrapped
+    /// in an `ae_key_decrypt` span that records whether the ciphertext authenticated in `success`
+    /// and the duration in `elapsed_us`. The plaintext and the key are never recorded.
+    #[cfg(not(target_os = "solana"))]
+    fn decrypt_bytes_with_cipher(
+        cipher: &Aes128GcmSiv,
+        nonce: &Nonce,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, DecryptError> {
+        let decrypt = || {
+            cipher
+                .decrypt(
+                    &(*nonce).into(),
+                    Payload {
+                        msg: ciphertext,
+                        aad,
+                    },
+                )
+                .map_err(|_| DecryptError::AuthenticationFailed)
+        };
+
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::debug_span!(
+                "ae_key_decrypt",
+                success = tracing::field::Empty,
+                elapsed_us = tracing::field::Empty,
+            );
+            let _guard = span.enter();
+            let start = std::time::Instant::now();
+            let plaintext = decrypt();
+            span.record("success", plaintext.is_ok());
+            span.record("elapsed_us", start.elapsed().as_micros() as u64);
+            plaintext
         }
+        #[cfg(not(feature = "tracing"))]
+        decrypt()
+    }
+
+    #[cfg(not(target_os = "solana"))]
+    fn decrypt(key: &AeKey, ct: &AeCiphertext) -> Option<u64> {
+        Self::decrypt_u64(key, &ct.nonce, ct.ciphertext.as_ref())
+    }
+
+    /// Decrypt a ciphertext and interpret the plaintext as a `u64` amount.
+    ///
+    /// Returns `None` if the ciphertext fails to authenticate or if the plaintext is not exactly 8
+    /// bytes.
+    #[cfg(not(target_os = "solana"))]
+    fn decrypt_u64(key: &AeKey, nonce: &Nonce, ciphertext: &[u8]) -> Option<u64> {
+        Self::decrypt_u64_with_aad(key, nonce, ciphertext, &[])
+    }
+
+    /// Decrypt a ciphertext with associated data `aad` and interpret the plaintext as a `u64`
+    /// amount.
+    #[cfg(not(target_os = "solana"))]
+    fn decrypt_u64_with_aad(
+        key: &AeKey,
+        nonce: &Nonce,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Option<u64> {
+        Self::try_decrypt_u64_with_aad(key, nonce, ciphertext, aad).ok()
+    }
+
+    /// Decrypt a ciphertext with associated data `aad`, reporting the reason of a failure.
+    #[cfg(not(target_os = "solana"))]
+    fn try_decrypt_u64_with_aad(
+        key: &AeKey,
+        nonce: &Nonce,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<u64, DecryptError> {
+        Self::try_decrypt_u64_with_cipher(&Aes128GcmSiv::new(&key.0.into()), nonce, ciphertext, aad)
+    }
+
+    /// Decrypt a ciphertext with associated data `aad` under a cipher instance of the key,
+    /// reporting the reason of a failure.
+    #[cfg(not(target_os = "solana"))]
+    fn try_decrypt_u64_with_cipher(
+        cipher: &Aes128GcmSiv,
+        nonce: &Nonce,
+        ciphertext: &[u8],
+        aad: &[u8],
+    ) -> Result<u64, DecryptError> {
+        let plaintext = Self::decrypt_bytes_with_cipher(cipher, nonce, ciphertext, aad)?;
+
+        let amount_bytes: [u8; 8] = plaintext
+            .try_into()
+            .map_err(|_| DecryptError::MalformedCiphertext)?;
+        Ok(u64::from_le_bytes(amount_bytes))
     }
 }
 
+/// Key for the authenticated encryption scheme.
+///
+/// Instances of authenticated encryption key are zeroized on drop. This is a best-effort
+/// guarantee: the compiler may still leave copies of the key bytes in memory, e.g. when the key is
+/// moved.
 #[derive(Debug, Zeroize)]
-pub struct AeKey([u8; 16]);
+#[zeroize(drop)]
+pub struct AeKey(pub(crate) [u8; 16]);
 impl AeKey {
     pub fn new(signer: &dyn Signer, address: &Pubkey) -> Result<Self, SignerError> {
         let message = Message::new(
@@ -95,23 +261,584 @@ impl AeKey {
         // Some `Signer` implementations return the default signature, which is not suitable for
         // use as key material
         if bool::from(signature.as_ref().ct_eq(Signature::default().as_ref())) {
-            Err(SignerError::Custom("Rejecting default signature".into()))
-        } else {
-            Ok(AeKey(signature.as_ref()[..16].try_into().unwrap()))
+            return Err(SignerError::Custom("Rejecting default signature".into()));
+        }
+
+        // `Signature` does not implement `Zeroize`, so the key is derived from a local copy of the
+        // signature bytes that is zeroized afterwards
+        let mut signature_bytes = [0_u8; 64];
+        signature_bytes.copy_from_slice(signature.as_ref());
+        let key = AeKey(signature_bytes[..16].try_into().unwrap());
+        signature_bytes.zeroize();
+
+        Ok(key)
+    }
+
+    /// Derive a key from a signer that is shared across threads.
+    ///
+    /// The derived key is identical to that of `AeKey::new` for the same signer and address.
+    pub fn new_shared(
+        signer: Arc<dyn Signer + Send + Sync>,
+        address: &Pubkey,
+    ) -> Result<Self, SignerError> {
+        Self::new(signer.as_ref(), address)
+    }
+
+    /// Derive an ephemeral key from a transaction signature and a context tag.
+    ///
+    /// The key is the first 16 bytes of `Sha3_512(signature || context)`. Distinct context tags
+    /// yield independent keys for the same signature.
+    pub fn from_transaction_signature(
+        signature: &Signature,
+        context: &[u8],
+    ) -> Result<Self, AuthenticatedEncryptionError> {
+        if bool::from(signature.as_ref().ct_eq(Signature::default().as_ref())) {
+            return Err(AuthenticatedEncryptionError::DefaultSignature);
         }
+
+        let mut hasher = Sha3_512::new();
+        hasher.update(signature.as_ref());
+        hasher.update(context);
+        let mut result = hasher.finalize();
+        let key = AeKey(result[..16].try_into().unwrap());
+        result.as_mut_slice().zeroize();
+
+        Ok(key)
     }
 
     pub fn random<T: RngCore + CryptoRng>(rng: &mut T) -> Self {
         AuthenticatedEncryption::keygen(rng)
     }
 
+    /// Reconstruct a key from two shares produced by `AeKey::split`.
+    ///
+    /// This is a 2-of-2 additive (XOR) secret sharing of the key bytes. Both shares are required
+    /// to recover the key; this is not a threshold scheme.
+    pub fn from_shares(share_a: &[u8; 16], share_b: &[u8; 16]) -> Self {
+        let mut key = [0_u8; 16];
+        for (byte, (a, b)) in key.iter_mut().zip(share_a.iter().zip(share_b.iter())) {
+            *byte = a ^ b;
+        }
+        AeKey(key)
+    }
+
+    /// Split the key into two shares such that both are required to reconstruct the key via
+    /// `AeKey::from_shares`.
+    ///
+    /// The first share is sampled uniformly at random and the second share is its XOR complement
+    /// with respect to the key, so each share alone is independent of the key.
+    pub fn split<T: RngCore + CryptoRng>(&self, rng: &mut T) -> ([u8; 16], [u8; 16]) {
+        let share_a = rng.gen::<[u8; 16]>();
+        let mut share_b = [0_u8; 16];
+        for (byte, (a, k)) in share_b.iter_mut().zip(share_a.iter().zip(self.0.iter())) {
+            *byte = a ^ k;
+        }
+        (share_a, share_b)
+    }
+
+    /// A short fingerprint of the key that can be used to correlate logs without exposing the key.
+    ///
+    /// The fingerprint consists of the first 8 hex characters of the `Sha3_512` hash of the key.
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha3_512::new();
+        hasher.update(self.0);
+        let result = hasher.finalize();
+
+        result[..4]
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect()
+    }
+
+    /// Encode the key as a base64 block between `-----BEGIN AE KEY-----` and
+    /// `-----END AE KEY-----` lines.
+    pub fn to_armored(&self) -> String {
+        let mut encoded = [0_u8; 24];
+        BASE64_STANDARD
+            .encode_slice(self.0, &mut encoded)
+            .expect("base64 encoding of 16 bytes");
+        let armored = format!(
+            "{AE_KEY_ARMOR_BEGIN}\n{}\n{AE_KEY_ARMOR_END}\n",
+            core::str::from_utf8(&encoded).unwrap()
+        );
+        encoded.zeroize();
+        armored
+    }
+
+    /// Decode a key from the armored format of `to_armored`.
+    ///
+    /// Returns `None` if the header or footer lines are missing or if the base64 block is not a
+    /// valid key.
+    pub fn from_armored(armored: &str) -> Option<Self> {
+        let mut lines = armored.trim().lines().map(str::trim);
+        if lines.next()? != AE_KEY_ARMOR_BEGIN {
+            return None;
+        }
+        let encoded = lines.next()?;
+        if lines.next()? != AE_KEY_ARMOR_END || lines.next().is_some() {
+            return None;
+        }
+
+        let mut decoded = BASE64_STANDARD.decode(encoded).ok()?;
+        let key = decoded.as_slice().try_into().ok().map(AeKey);
+        decoded.as_mut_slice().zeroize();
+        key
+    }
+
+    /// Read a base64-encoded key from the environment variable `var_name`.
+    ///
+    /// The intermediate copies of the key are zeroized. Note that environment variables are
+    /// visible to the whole process and possibly to other processes of the same user, so they are
+    /// not an ideal place to store secrets.
+    pub fn from_env(var_name: &str) -> Result<Self, Box<dyn error::Error>> {
+        let mut encoded = std::env::var(var_name)?.into_bytes();
+        let decoded = BASE64_STANDARD.decode(&encoded);
+        encoded.as_mut_slice().zeroize();
+
+        let mut decoded = decoded?;
+        let key = decoded.as_slice().try_into().map(AeKey);
+        decoded.as_mut_slice().zeroize();
+
+        key.map_err(|_| "Invalid AeKey length".into())
+    }
+
+    /// Write the key as a JSON byte array in the style of a Solana keypair file.
+    ///
+    /// A Solana keypair file is a JSON array of the 64 bytes of an ed25519 keypair. An `AeKey`
+    /// is not a signing key, so the key bytes are written after a 16-byte marker
+    /// `AE_KEY_FILE_MARKER` as a JSON array of 32 bytes. Solana tools reject the file as a signing
+    /// keypair, and `read_from_solana_format` rejects signing keypair files.
+    pub fn write_as_solana_format<W: Write>(
+        &self,
+        writer: &mut W,
+    ) -> Result<String, Box<dyn error::Error>> {
+        let mut bytes = [0_u8; 32];
+        bytes[..16].copy_from_slice(AE_KEY_FILE_MARKER);
+        bytes[16..].copy_from_slice(&self.0);
+        let json = serde_json::to_string(&bytes[..]);
+        bytes.zeroize();
+
+        let json = json?;
+        writer.write_all(json.as_bytes())?;
+        Ok(json)
+    }
+
+    /// Read a key that is written with `write_as_solana_format`.
+    pub fn read_from_solana_format<R: Read>(reader: &mut R) -> Result<Self, Box<dyn error::Error>> {
+        let mut bytes: Vec<u8> = serde_json::from_reader(reader)?;
+        let key = if bytes.len() != 32 {
+            Err("Not an AeKey file: invalid length")
+        } else if &bytes[..16] != AE_KEY_FILE_MARKER {
+            Err("Not an AeKey file: missing marker")
+        } else {
+            Ok(AeKey(bytes[16..].try_into().unwrap()))
+        };
+        bytes.as_mut_slice().zeroize();
+        key.map_err(|err| err.into())
+    }
+
+    /// Derive a key from a seed, rejecting obviously weak seeds.
+    ///
+    /// In addition to the length check of `from_seed`, the seed must contain at least 8 distinct
+    /// byte values. This guards against seeds such as all-zero or repeating byte patterns. It is a
+    /// simple heuristic and not an entropy estimator: a seed that passes the check is not
+    /// necessarily secure.
+    pub fn from_seed_checked(seed: &[u8]) -> Result<Self, Box<dyn error::Error>> {
+        const MINIMUM_DISTINCT_SEED_BYTES: usize = 8;
+
+        let mut seen = [false; 256];
+        for byte in seed {
+            seen[*byte as usize] = true;
+        }
+        if seen.iter().filter(|seen| **seen).count() < MINIMUM_DISTINCT_SEED_BYTES {
+            return Err("Seed has too few distinct bytes".into());
+        }
+
+        Self::from_seed(seed)
+    }
+
+    /// Derive a key from a low-entropy password using Argon2id.
+    ///
+    /// The `salt` must be at least 8 bytes. Use `SeedDerivable::from_seed` for high-entropy seeds
+    /// instead.
+    #[cfg(feature = "argon2")]
+    pub fn from_password(password: &str, salt: &[u8]) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut key = [0_u8; 16];
+        argon2::Argon2::default()
+            .hash_password_into(password.as_bytes(), salt, &mut key)
+            .map_err(|err| err.to_string())?;
+        Ok(AeKey(key))
+    }
+
+    /// Encrypt an amount under a fresh nonce.
+    ///
+    /// With the `tracing` feature, the encryption is wrapped in an `ae_key_encrypt` span that
+    /// records the duration in `elapsed_us`. The amount and the key are never recorded.
     pub fn encrypt(&self, amount: u64) -> AeCiphertext {
+        #[cfg(feature = "tracing")]
+        {
+            let span = tracing::debug_span!("ae_key_encrypt", elapsed_us = tracing::field::Empty);
+            let _guard = span.enter();
+            let start = std::time::Instant::now();
+            let ct = AuthenticatedEncryption::encrypt(self, amount);
+            span.record("elapsed_us", start.elapsed().as_micros() as u64);
+            ct
+        }
+        #[cfg(not(feature = "tracing"))]
         AuthenticatedEncryption::encrypt(self, amount)
     }
 
+    /// Encrypt an amount with a nonce that is derived from the key, the amount, and a counter.
+    ///
+    /// The nonce is the first 12 bytes of `Sha3_512(key || amount || counter)`, where the amount
+    /// and the counter are encoded in little-endian. Encrypting the same amount with the same
+    /// counter always produces the same ciphertext, which makes writes idempotent. The tradeoff
+    /// is that ciphertexts are linkable: anyone can tell whether two ciphertexts under the same key
+    /// encrypt the same amount with the same counter. Callers must use a distinct counter for each
+    /// encryption that should not be linkable.
+    pub fn encrypt_deterministic(&self, amount: u64, counter: u64) -> AeCiphertext {
+        let nonce = self.deterministic_nonce(amount, counter);
+        AuthenticatedEncryption::encrypt_with_nonce(self, amount, nonce)
+    }
+
+    /// Check whether a ciphertext has the nonce of `encrypt_deterministic` for `amount` and
+    /// `counter`.
+    ///
+    /// Only the nonce is compared; the ciphertext is not decrypted.
+    pub fn is_deterministic_for(&self, ct: &AeCiphertext, amount: u64, counter: u64) -> bool {
+        bool::from(self.deterministic_nonce(amount, counter).ct_eq(&ct.nonce))
+    }
+
+    fn deterministic_nonce(&self, amount: u64, counter: u64) -> Nonce {
+        let mut hasher = Sha3_512::new();
+        hasher.update(self.0);
+        hasher.update(amount.to_le_bytes());
+        hasher.update(counter.to_le_bytes());
+        let result = hasher.finalize();
+
+        result[..12].try_into().unwrap()
+    }
+
+    /// Encrypt an amount with a nonce from `source`, reporting the nonce source and the nonce
+    /// to `trace`.
+    ///
+    /// The ciphertext format is identical to that of `encrypt`. A supplied nonce must never be
+    /// reused for the same key.
+    pub fn encrypt_traced<F>(&self, amount: u64, source: NonceSource, trace: F) -> AeCiphertext
+    where
+        F: FnOnce(&NonceSource, &Nonce),
+    {
+        let nonce = match source {
+            NonceSource::Os => OsRng.gen::<[u8; 12]>(),
+            NonceSource::Supplied(nonce) => nonce,
+        };
+        trace(&source, &nonce);
+
+        AuthenticatedEncryption::encrypt_with_nonce(self, amount, nonce)
+    }
+
+    /// Encrypt the new available balance of a confidential token account.
+    ///
+    /// The resulting ciphertext is the `decryptable_available_balance` that the token program
+    /// expects in the instructions that update the available balance, e.g. when applying the
+    /// pending balance to the available balance.
+    pub fn encrypt_available_balance(&self, new_balance: u64) -> AeCiphertext {
+        self.encrypt(new_balance)
+    }
+
+    /// Encrypt an amount only if it does not exceed `max`.
+    ///
+    /// Returns `None` if `amount > max`.
+    pub fn encrypt_checked(&self, amount: u64, max: u64) -> Option<AeCiphertext> {
+        if amount > max {
+            None
+        } else {
+            Some(self.encrypt(amount))
+        }
+    }
+
+    /// Decrypt a ciphertext, returning `None` if the ciphertext fails to decrypt.
+    ///
+    /// With the `tracing` feature, the decryption is wrapped in an `ae_key_decrypt` span that
+    /// records whether the ciphertext authenticated in `success` and the duration in
+    /// `elapsed_us`. The same span wraps every other decryption with the key, e.g. through
+    /// `AeCiphertext::decrypt` or `can_decrypt`. The amount and the key are never recorded.
     pub fn decrypt(&self, ct: &AeCiphertext) -> Option<u64> {
         AuthenticatedEncryption::decrypt(self, ct)
     }
+
+    /// Decrypt a ciphertext, returning the amount only if it fits in `bits` bits.
+    ///
+    /// This flags amounts that would not pass a range proof for `bits` bits. A `bits` of 64 or
+    /// more accepts any amount.
+    pub fn decrypt_bounded(&self, ct: &AeCiphertext, bits: u32) -> Option<u64> {
+        let amount = self.decrypt(ct)?;
+        if bits < u64::BITS && amount >> bits != 0 {
+            return None;
+        }
+        Some(amount)
+    }
+
+    /// Derive a viewing key for an auditor.
+    ///
+    /// The viewing key is the first 16 bytes of `Sha3_512("AeKeyViewingKey" || key)`, so it is
+    /// deterministic but cannot be used to recover the key itself. Authenticated encryption is
+    /// symmetric, so the viewing key is simply a different key: it cannot decrypt ciphertexts
+    /// that are encrypted under this key. A balance becomes readable to the holder of the
+    /// viewing key only once it is explicitly re-encrypted under the viewing key.
+    pub fn derive_viewing_key(&self) -> AeKey {
+        let mut hasher = Sha3_512::new();
+        hasher.update(b"AeKeyViewingKey");
+        hasher.update(self.0);
+        let mut result = hasher.finalize();
+        let viewing_key = AeKey(result[..16].try_into().unwrap());
+        result.as_mut_slice().zeroize();
+
+        viewing_key
+    }
+
+    /// Compute an HMAC-SHA3-256 tag of `data`.
+    ///
+    /// The HMAC key is a subkey that is derived from the key with `Sha3_256` and a domain
+    /// separator, so the tag does not use the encryption key itself. This can be used to
+    /// authenticate metadata that is stored in plaintext.
+    pub fn mac(&self, data: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha3_256::new();
+        hasher.update(b"AeKeyMac");
+        hasher.update(self.0);
+        let mut subkey = hasher.finalize();
+
+        let mut mac = HmacSha3_256::new_from_slice(&subkey).unwrap();
+        mac.update(data);
+        subkey.as_mut_slice().zeroize();
+
+        mac.finalize().into_bytes().into()
+    }
+
+    /// Verify an HMAC-SHA3-256 tag of `data` that is computed with `mac` in constant time.
+    pub fn verify_mac(&self, data: &[u8], tag: &[u8; 32]) -> bool {
+        bool::from(self.mac(data).ct_eq(tag))
+    }
+
+    /// Check whether the ciphertext decrypts under the key without returning the amount.
+    ///
+    /// The decrypted amount is zeroized before returning.
+    pub fn can_decrypt(&self, ct: &AeCiphertext) -> bool {
+        match AuthenticatedEncryption::decrypt(self, ct) {
+            Some(mut amount) => {
+                amount.zeroize();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Encrypt a slice of amounts, each under a fresh nonce.
+    pub fn encrypt_batch(&self, amounts: &[u64]) -> Vec<AeCiphertext> {
+        amounts.iter().map(|amount| self.encrypt(*amount)).collect()
+    }
+
+    /// Encrypt a slice of amounts in parallel, preserving the order of the amounts.
+    ///
+    /// The nonces are sampled with `OsRng`, which draws from the operating system independently
+    /// on each thread.
+    #[cfg(feature = "rayon")]
+    pub fn encrypt_batch_par(&self, amounts: &[u64]) -> Vec<AeCiphertext> {
+        use rayon::prelude::*;
+
+        amounts
+            .par_iter()
+            .map(|amount| self.encrypt(*amount))
+            .collect()
+    }
+
+    /// Randomly partition an amount into `parts` summands and encrypt each summand under a fresh
+    /// nonce.
+    ///
+    /// The summands are non-negative and add up to exactly `amount`. The amount can be recovered
+    /// with `decrypt_join`.
+    ///
+    /// Panics if `parts` is zero.
+    pub fn encrypt_split<T: RngCore + CryptoRng>(
+        &self,
+        amount: u64,
+        parts: usize,
+        rng: &mut T,
+    ) -> Vec<AeCiphertext> {
+        assert!(parts > 0, "an amount must be split into at least one part");
+
+        let mut cuts: Vec<u64> = (1..parts)
+            .map(|_| match amount.checked_add(1) {
+                Some(bound) => rng.gen_range(0, bound),
+                None => rng.gen::<u64>(),
+            })
+            .collect();
+        cuts.sort_unstable();
+
+        let mut previous = 0;
+        cuts.into_iter()
+            .chain(std::iter::once(amount))
+            .map(|cut| {
+                let ct = self.encrypt(cut - previous);
+                previous = cut;
+                ct
+            })
+            .collect()
+    }
+
+    /// Decrypt ciphertexts produced by `encrypt_split` and return the sum of their amounts.
+    ///
+    /// Returns `None` if any ciphertext fails to decrypt or if the sum overflows.
+    pub fn decrypt_join(&self, cts: &[AeCiphertext]) -> Option<u64> {
+        cts.iter()
+            .try_fold(0_u64, |sum, ct| sum.checked_add(self.decrypt(ct)?))
+    }
+
+    /// Encrypt an amount with associated data `aad`.
+    ///
+    /// The associated data is authenticated but not encrypted, and it is not part of the
+    /// ciphertext. The same associated data must be supplied to `decrypt_with_aad`.
+    pub fn encrypt_with_aad(&self, amount: u64, aad: &[u8]) -> AeCiphertext {
+        let nonce: Nonce = OsRng.gen::<[u8; 12]>();
+        AuthenticatedEncryption::encrypt_with_nonce_and_aad(self, amount, nonce, aad)
+    }
+
+    /// Decrypt a ciphertext that was encrypted with associated data `aad`.
+    ///
+    /// Returns `None` if `aad` does not match the associated data of the encryption.
+    pub fn decrypt_with_aad(&self, ct: &AeCiphertext, aad: &[u8]) -> Option<u64> {
+        AuthenticatedEncryption::decrypt_u64_with_aad(self, &ct.nonce, ct.ciphertext.as_ref(), aad)
+    }
+
+    /// Encrypt an amount that is bound to an expiry unix timestamp.
+    ///
+    /// The big-endian encoding of `expires_at_unix` is used as the associated data of the
+    /// encryption. The expiry is not part of the ciphertext and must be supplied again to
+    /// `decrypt_with_expiry`.
+    pub fn encrypt_with_expiry(&self, amount: u64, expires_at_unix: i64) -> AeCiphertext {
+        self.encrypt_with_aad(amount, &expires_at_unix.to_be_bytes())
+    }
+
+    /// Decrypt a ciphertext that was encrypted with `encrypt_with_expiry`.
+    ///
+    /// Returns `None` if `now_unix` is past `expires_at_unix`, if `expires_at_unix` is not the
+    /// expiry of the encryption, or if the ciphertext fails to authenticate.
+    pub fn decrypt_with_expiry(
+        &self,
+        ct: &AeCiphertext,
+        expires_at_unix: i64,
+        now_unix: i64,
+    ) -> Option<u64> {
+        if now_unix > expires_at_unix {
+            return None;
+        }
+        self.decrypt_with_aad(ct, &expires_at_unix.to_be_bytes())
+    }
+
+    /// Decrypt a slice of ciphertexts and sum the decrypted amounts.
+    ///
+    /// The sum is accumulated as a `u128` so that it cannot overflow. Returns `None` if any of the
+    /// ciphertexts fails to decrypt.
+    pub fn decrypt_sum(&self, cts: &[AeCiphertext]) -> Option<u128> {
+        cts.iter()
+            .try_fold(0_u128, |sum, ct| Some(sum + self.decrypt(ct)? as u128))
+    }
+
+    /// Encrypt an amount together with a 1-byte version under a fresh nonce.
+    ///
+    /// The plaintext is the version followed by the amount in little-endian, so the version is
+    /// authenticated along with the amount. This is a different wire format than that of
+    /// `encrypt`: the ciphertext is 25 bytes instead of 24, and it is returned as a
+    /// `VersionedAeCiphertext`.
+    pub fn encrypt_versioned(&self, amount: u64, version: u8) -> VersionedAeCiphertext {
+        let nonce: Nonce = OsRng.gen::<[u8; 12]>();
+
+        let mut plaintext = [0_u8; 9];
+        plaintext[0] = version;
+        plaintext[1..].copy_from_slice(&amount.to_le_bytes());
+
+        let ciphertext = AuthenticatedEncryption::encrypt_bytes(self, &nonce, &plaintext, &[]);
+        plaintext.zeroize();
+
+        VersionedAeCiphertext {
+            nonce,
+            ciphertext: ciphertext.try_into().unwrap(),
+        }
+    }
+
+    /// Decrypt a ciphertext produced by `encrypt_versioned`, returning the version and the
+    /// amount.
+    pub fn decrypt_versioned(&self, ct: &VersionedAeCiphertext) -> Option<(u8, u64)> {
+        let mut plaintext =
+            AuthenticatedEncryption::decrypt_bytes(self, &ct.nonce, ct.ciphertext.as_ref(), &[])
+                .ok()?;
+
+        let decoded = match plaintext.split_first() {
+            Some((version, amount_bytes)) if amount_bytes.len() == 8 => Some((
+                *version,
+                u64::from_le_bytes(amount_bytes.try_into().unwrap()),
+            )),
+            _ => None,
+        };
+        plaintext.as_mut_slice().zeroize();
+        decoded
+    }
+
+    /// Encrypt an amount under a fresh nonce and tag the ciphertext with the key rotation epoch
+    /// of the key.
+    ///
+    /// The epoch is not authenticated: it only tells an `EpochKeystore` which key to use.
+    pub fn encrypt_with_epoch(&self, amount: u64, epoch: u32) -> EpochCiphertext {
+        EpochCiphertext {
+            epoch,
+            ciphertext: self.encrypt(amount),
+        }
+    }
+
+    /// Decrypt a ciphertext in the original format of a 12-byte nonce and a 24-byte ciphertext.
+    ///
+    /// This is currently identical to `decrypt`. It exists so that ciphertexts in the original
+    /// format remain decryptable if the default ciphertext format changes.
+    pub fn decrypt_legacy_v0(&self, ct: &AeCiphertext) -> Option<u64> {
+        AuthenticatedEncryption::decrypt(self, ct)
+    }
+
+    /// Decrypt an array of ciphertexts, pairing each decrypted amount with its index in the array.
+    ///
+    /// Ciphertexts that fail to decrypt are reported as `None` at their index.
+    pub fn decrypt_array_indexed<const N: usize>(
+        &self,
+        cts: &[AeCiphertext; N],
+    ) -> [(usize, Option<u64>); N] {
+        std::array::from_fn(|i| (i, self.decrypt(&cts[i])))
+    }
+
+    /// Lazily decrypt a sequence of 36-byte encoded ciphertexts, e.g. from a memory-mapped file.
+    ///
+    /// The records are read in place and decrypted with a single cipher instance. Returns
+    /// `InvalidCiphertextDataLength` if the length of `data` is not a multiple of 36.
+    pub fn decrypt_mmap<'a>(
+        &self,
+        data: &'a [u8],
+    ) -> Result<impl Iterator<Item = Option<u64>> + 'a, AuthenticatedEncryptionError> {
+        const CIPHERTEXT_LEN: usize = 36;
+
+        if data.len() % CIPHERTEXT_LEN != 0 {
+            return Err(AuthenticatedEncryptionError::InvalidCiphertextDataLength);
+        }
+
+        let cipher = Aes128GcmSiv::new(&self.0.into());
+        Ok(data.chunks_exact(CIPHERTEXT_LEN).map(move |record| {
+            let ct = AeCiphertext::from_bytes(record)?;
+            AuthenticatedEncryption::try_decrypt_u64_with_cipher(
+                &cipher,
+                &ct.nonce,
+                ct.ciphertext.as_ref(),
+                &[],
+            )
+            .ok()
+        }))
+    }
 }
 
 impl EncodableKey for AeKey {
@@ -161,13 +888,25 @@ impl SeedDerivable for AeKey {
     }
 }
 
+/// The source of the nonce for an authenticated encryption.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NonceSource {
+    /// The nonce is sampled using `OsRng`
+    Os,
+    /// The nonce is supplied by the caller
+    Supplied(Nonce),
+}
+
 /// For the purpose of encrypting balances for the spl token accounts, the nonce and ciphertext
 /// sizes should always be fixed.
 pub type Nonce = [u8; 12];
 pub type Ciphertext = [u8; 24];
 
 /// Authenticated encryption nonce and ciphertext
-#[derive(Debug, Default, Clone)]
+///
+/// The nonce and ciphertext are fixed-size arrays, so the `bincode` encoding of an `AeCiphertext`
+/// is exactly 36 bytes without any length prefixes.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
 pub struct AeCiphertext {
     pub nonce: Nonce,
     pub ciphertext: Ciphertext,
@@ -177,6 +916,16 @@ impl AeCiphertext {
         AuthenticatedEncryption::decrypt(key, self)
     }
 
+    /// Decrypt the ciphertext, reporting the reason if the ciphertext fails to decrypt.
+    pub fn try_decrypt(&self, key: &AeKey) -> Result<u64, DecryptError> {
+        AuthenticatedEncryption::try_decrypt_u64_with_aad(
+            key,
+            &self.nonce,
+            self.ciphertext.as_ref(),
+            &[],
+        )
+    }
+
     pub fn to_bytes(&self) -> [u8; 36] {
         let mut buf = [0_u8; 36];
         buf[..12].copy_from_slice(&self.nonce);
@@ -197,6 +946,214 @@ impl AeCiphertext {
             ciphertext: *ciphertext,
         })
     }
+
+    /// Return a copy of the ciphertext with the nonce replaced by `nonce`.
+    ///
+    /// The authentication tag is not recomputed, so the resulting ciphertext fails to decrypt
+    /// unless `nonce` is the original nonce. This is a test helper for constructing nonce
+    /// collisions.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn with_nonce(&self, nonce: Nonce) -> AeCiphertext {
+        AeCiphertext {
+            nonce,
+            ciphertext: self.ciphertext,
+        }
+    }
+
+    /// Encode the ciphertext in base64 with the URL-safe alphabet and without padding.
+    ///
+    /// Unlike the `Display` encoding, the output can be embedded in URLs without escaping.
+    pub fn to_base64_url(&self) -> String {
+        BASE64_URL_SAFE_NO_PAD.encode(self.to_bytes())
+    }
+
+    /// Decode a ciphertext that is encoded with `AeCiphertext::to_base64_url`.
+    pub fn from_base64_url(encoded: &str) -> Option<AeCiphertext> {
+        let bytes = BASE64_URL_SAFE_NO_PAD.decode(encoded).ok()?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Encode the nonce and ciphertext as separate base64 strings.
+    pub fn to_display(&self) -> AeCiphertextDisplay {
+        AeCiphertextDisplay {
+            nonce_base64: BASE64_STANDARD.encode(self.nonce),
+            ciphertext_base64: BASE64_STANDARD.encode(self.ciphertext),
+        }
+    }
+
+    /// Decode a ciphertext from the separate base64 encodings of its nonce and ciphertext.
+    ///
+    /// Returns `None` if either field is not valid base64 or does not decode to the expected
+    /// number of bytes.
+    pub fn from_display(display: &AeCiphertextDisplay) -> Option<AeCiphertext> {
+        let nonce = BASE64_STANDARD.decode(&display.nonce_base64).ok()?;
+        let ciphertext = BASE64_STANDARD.decode(&display.ciphertext_base64).ok()?;
+
+        Some(AeCiphertext {
+            nonce: nonce.try_into().ok()?,
+            ciphertext: ciphertext.try_into().ok()?,
+        })
+    }
+
+    /// Compare the ciphertext against a byte encoding in constant time.
+    ///
+    /// The input is copied into a zero-padded 36-byte buffer, and the buffer is always compared
+    /// against the full encoding of the ciphertext, so the comparison does the same work for
+    /// every input length. An input of the wrong length is never equal. The length of the input
+    /// itself is not secret: copying it takes time proportional to its length, so this only
+    /// protects the contents of the bytes.
+    pub fn ct_eq_bytes(&self, bytes: &[u8]) -> Choice {
+        let mut buf = [0_u8; 36];
+        let copy_len = bytes.len().min(buf.len());
+        buf[..copy_len].copy_from_slice(&bytes[..copy_len]);
+
+        let length_matches = Choice::from((bytes.len() == buf.len()) as u8);
+        buf.ct_eq(&self.to_bytes()) & length_matches
+    }
+
+    /// Select `a` if `choice` is 0 and `b` if `choice` is 1 in constant time.
+    ///
+    /// `AeCiphertext` is not `Copy` and therefore, cannot implement `ConditionallySelectable`.
+    /// The nonce and ciphertext bytes are selected individually instead.
+    pub fn conditional_select(a: &AeCiphertext, b: &AeCiphertext, choice: Choice) -> AeCiphertext {
+        let mut selected = AeCiphertext::default();
+        for (byte, (a, b)) in selected.nonce.iter_mut().zip(a.nonce.iter().zip(&b.nonce)) {
+            *byte = u8::conditional_select(a, b, choice);
+        }
+        for (byte, (a, b)) in selected
+            .ciphertext
+            .iter_mut()
+            .zip(a.ciphertext.iter().zip(&b.ciphertext))
+        {
+            *byte = u8::conditional_select(a, b, choice);
+        }
+        selected
+    }
+
+    /// Serialize a slice of ciphertexts into a concatenation of their 36-byte encodings.
+    pub fn serialize_many(cts: &[AeCiphertext]) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(36 * cts.len());
+        for ct in cts {
+            buf.extend_from_slice(&ct.to_bytes());
+        }
+        buf
+    }
+
+    /// Deserialize a concatenation of 36-byte ciphertext encodings.
+    ///
+    /// Returns `None` if the length of `bytes` is not a multiple of 36.
+    pub fn deserialize_many(bytes: &[u8]) -> Option<Vec<AeCiphertext>> {
+        if bytes.len() % 36 != 0 {
+            return None;
+        }
+        bytes
+            .chunks_exact(36)
+            .map(AeCiphertext::from_bytes)
+            .collect()
+    }
+
+    /// Deserialize a concatenation of 36-byte ciphertext encodings, skipping malformed records.
+    ///
+    /// Returns the ciphertexts of the complete records together with the byte offsets of the
+    /// records that could not be parsed, which is a trailing partial record if the length of
+    /// `bytes` is not a multiple of 36. Only the record lengths are checked; whether a
+    /// ciphertext decrypts must be checked separately.
+    pub fn parse_recoverable(bytes: &[u8]) -> (Vec<AeCiphertext>, Vec<usize>) {
+        let mut cts = Vec::with_capacity(bytes.len() / 36);
+        let mut invalid_offsets = vec![];
+        for (index, record) in bytes.chunks(36).enumerate() {
+            match AeCiphertext::from_bytes(record) {
+                Some(ct) => cts.push(ct),
+                None => invalid_offsets.push(index * 36),
+            }
+        }
+        (cts, invalid_offsets)
+    }
+
+    /// Compute the root of a binary Merkle tree over a set of ciphertexts.
+    ///
+    /// The leaves are `Sha3_256(ct.to_bytes())` and each internal node is
+    /// `Sha3_256(left || right)`. If a level has an odd number of nodes, the last node is paired
+    /// with itself. The root of a single ciphertext is its leaf, and the root of an empty set is
+    /// all zeros.
+    pub fn merkle_root(cts: &[AeCiphertext]) -> [u8; 32] {
+        let mut level: Vec<[u8; 32]> = cts
+            .iter()
+            .map(|ct| Sha3_256::digest(&ct.to_bytes()).into())
+            .collect();
+        if level.is_empty() {
+            return [0; 32];
+        }
+
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| {
+                    let left = &pair[0];
+                    let right = pair.get(1).unwrap_or(left);
+                    let mut hasher = Sha3_256::new();
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().into()
+                })
+                .collect();
+        }
+        level[0]
+    }
+}
+
+/// Authenticated encryption nonce and ciphertext of an amount together with a 1-byte version.
+///
+/// The ciphertext is one byte longer than that of `AeCiphertext`, so the two formats are not
+/// interchangeable.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct VersionedAeCiphertext {
+    pub nonce: Nonce,
+    pub ciphertext: [u8; 25],
+}
+
+/// An `AeCiphertext` tagged with the key rotation epoch of the key that encrypted it.
+///
+/// The epoch is routing metadata that is not covered by the authentication tag, so it can be
+/// modified without detection. Modifying the epoch only causes the wrong key to be selected, in
+/// which case the ciphertext fails to decrypt.
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct EpochCiphertext {
+    pub epoch: u32,
+    pub ciphertext: AeCiphertext,
+}
+impl EpochCiphertext {
+    /// Encode the epoch in little-endian followed by the 36-byte encoding of the ciphertext.
+    pub fn to_bytes(&self) -> [u8; 40] {
+        let mut buf = [0_u8; 40];
+        buf[..4].copy_from_slice(&self.epoch.to_le_bytes());
+        buf[4..].copy_from_slice(&self.ciphertext.to_bytes());
+        buf
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<EpochCiphertext> {
+        if bytes.len() != 40 {
+            return None;
+        }
+
+        let bytes = array_ref![bytes, 0, 40];
+        let (epoch, ciphertext) = array_refs![bytes, 4, 36];
+
+        Some(EpochCiphertext {
+            epoch: u32::from_le_bytes(*epoch),
+            ciphertext: AeCiphertext::from_bytes(ciphertext)?,
+        })
+    }
+}
+
+/// An `AeCiphertext` with its nonce and ciphertext encoded as separate base64 strings.
+///
+/// Unlike the `Display` encoding of `AeCiphertext`, which concatenates the nonce and ciphertext,
+/// this representation gives clients field-level access to the two parts.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AeCiphertextDisplay {
+    pub nonce_base64: String,
+    pub ciphertext_base64: String,
 }
 
 impl fmt::Display for AeCiphertext {
@@ -205,6 +1162,114 @@ impl fmt::Display for AeCiphertext {
     }
 }
 
+/// Known-answer test vectors of the authenticated encryption of amounts.
+///
+/// Each vector consists of a key, an amount, a nonce, and the expected ciphertext of the amount
+/// encrypted under the key and the nonce. Other implementations can use the vectors to check
+/// byte-compatibility with `AeCiphertext`. The first vector is the 8-byte plaintext vector of
+/// AES-128-GCM-SIV from RFC 8452.
+#[cfg(any(test, feature = "test-utils"))]
+pub fn ae_test_vectors() -> Vec<(AeKey, u64, Nonce, AeCiphertext)> {
+    vec![
+        // RFC 8452, Appendix C.1
+        (
+            AeKey([
+                0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ]),
+            1,
+            [
+                0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+            AeCiphertext {
+                nonce: [
+                    0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                ],
+                ciphertext: [
+                    0xb5, 0xd8, 0x39, 0x33, 0x0a, 0xc7, 0xb7, 0x86, 0x57, 0x87, 0x82, 0xff, 0xf6,
+                    0x01, 0x3b, 0x81, 0x5b, 0x28, 0x7c, 0x22, 0x49, 0x3a, 0x36, 0x4c,
+                ],
+            },
+        ),
+        (
+            AeKey([
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                0x00, 0x00,
+            ]),
+            0,
+            [
+                0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+            ],
+            AeCiphertext {
+                nonce: [
+                    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                ],
+                ciphertext: [
+                    0x46, 0x74, 0x8b, 0xcd, 0xe5, 0x3a, 0x5f, 0x69, 0x75, 0xc8, 0x53, 0x7a, 0xa5,
+                    0xf6, 0xfb, 0x08, 0x50, 0x15, 0x06, 0x43, 0x14, 0x96, 0x01, 0x6a,
+                ],
+            },
+        ),
+        (
+            AeKey([
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+                0x0e, 0x0f,
+            ]),
+            1,
+            [
+                0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,
+            ],
+            AeCiphertext {
+                nonce: [
+                    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b,
+                ],
+                ciphertext: [
+                    0x6c, 0xf5, 0x2b, 0x4f, 0x5a, 0x66, 0xea, 0xf4, 0x42, 0xe7, 0x80, 0x44, 0xd2,
+                    0xb7, 0x1c, 0x59, 0x0e, 0x76, 0x39, 0x93, 0x95, 0xe3, 0x3c, 0x1a,
+                ],
+            },
+        ),
+        (
+            AeKey([
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                0xff, 0xff,
+            ]),
+            55,
+            [
+                0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+            ],
+            AeCiphertext {
+                nonce: [
+                    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                ],
+                ciphertext: [
+                    0x2e, 0xdc, 0x52, 0xf2, 0xd3, 0x1f, 0x5d, 0x0c, 0xde, 0x5d, 0x8f, 0x44, 0x50,
+                    0xa1, 0x2b, 0x29, 0x00, 0x1d, 0xa3, 0xb7, 0xfc, 0xc1, 0xce, 0xa6,
+                ],
+            },
+        ),
+        (
+            AeKey([
+                0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b, 0x1c, 0x1d,
+                0x1e, 0x1f,
+            ]),
+            u64::MAX,
+            [
+                0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c, 0x6d, 0x6e, 0x6f,
+            ],
+            AeCiphertext {
+                nonce: [
+                    0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c, 0x6d, 0x6e, 0x6f,
+                ],
+                ciphertext: [
+                    0xbe, 0xb1, 0x31, 0x80, 0x83, 0x04, 0xa6, 0xf0, 0x39, 0x5e, 0x68, 0xcf, 0x86,
+                    0x87, 0x53, 0xca, 0x4b, 0x8c, 0x1a, 0x25, 0xa9, 0x4b, 0x99, 0x49,
+                ],
+            },
+        ),
+    ]
+}
+
 #[cfg(test)]
 mod tests {
     use {
@@ -224,16 +1289,905 @@ mod tests {
     }
 
     #[test]
-    fn test_aes_new() {
-        let keypair1 = Keypair::new();
-        let keypair2 = Keypair::new();
+    fn test_aes_key_split_and_recombine() {
+        let key = AeKey::random(&mut OsRng);
 
-        assert_ne!(
-            AeKey::new(&keypair1, &Pubkey::default()).unwrap().0,
-            AeKey::new(&keypair2, &Pubkey::default()).unwrap().0,
-        );
+        let (share_a, share_b) = key.split(&mut OsRng);
+        assert_eq!(AeKey::from_shares(&share_a, &share_b).0, key.0);
+        assert_eq!(AeKey::from_shares(&share_b, &share_a).0, key.0);
 
-        let null_signer = NullSigner::new(&Pubkey::default());
-        assert!(AeKey::new(&null_signer, &Pubkey::default()).is_err());
+        // a single share is random and does not reveal the key
+        let (other_share_a, other_share_b) = key.split(&mut OsRng);
+        assert_ne!(share_a, other_share_a);
+        assert_ne!(share_b, other_share_b);
+        assert_ne!(share_a, key.0);
+        assert_ne!(share_b, key.0);
+
+        let amount = 55;
+        let ct = key.encrypt(amount);
+        let recombined_key = AeKey::from_shares(&other_share_a, &other_share_b);
+        assert_eq!(recombined_key.decrypt(&ct).unwrap(), amount);
+    }
+
+    #[test]
+    fn test_aes_encrypt_checked() {
+        let key = AeKey::random(&mut OsRng);
+        let max = 1_u64 << 48;
+
+        let ct = key.encrypt_checked(max - 1, max).unwrap();
+        assert_eq!(key.decrypt(&ct).unwrap(), max - 1);
+
+        let ct = key.encrypt_checked(max, max).unwrap();
+        assert_eq!(key.decrypt(&ct).unwrap(), max);
+
+        assert!(key.encrypt_checked(max + 1, max).is_none());
+    }
+
+    #[test]
+    fn test_aes_decrypt_array_indexed() {
+        let key = AeKey::random(&mut OsRng);
+
+        let mut cts = [key.encrypt(10), key.encrypt(20), key.encrypt(30)];
+        cts[1].ciphertext[0] ^= 1;
+
+        assert_eq!(
+            key.decrypt_array_indexed(&cts),
+            [(0, Some(10)), (1, None), (2, Some(30))]
+        );
+    }
+
+    #[test]
+    fn test_aes_key_fingerprint() {
+        let key = AeKey::random(&mut OsRng);
+        let other_key = AeKey::random(&mut OsRng);
+
+        let fingerprint = key.fingerprint();
+        assert_eq!(fingerprint.len(), 8);
+        assert_eq!(fingerprint, key.fingerprint());
+        assert_ne!(fingerprint, other_key.fingerprint());
+
+        let key_hex: String = key.0.iter().map(|byte| format!("{byte:02x}")).collect();
+        assert!(!key_hex.contains(&fingerprint));
+    }
+
+    #[test]
+    fn test_aes_ciphertext_serialization() {
+        let key = AeKey::random(&mut OsRng);
+        let ct = key.encrypt(55);
+
+        let bytes: [u8; 36] = ct.to_bytes();
+        let decoded = AeCiphertext::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert_eq!(decoded.decrypt(&key).unwrap(), 55);
+
+        assert_eq!(ct.to_string(), BASE64_STANDARD.encode(bytes));
+    }
+
+    #[test]
+    fn test_aes_key_zeroize_on_drop() {
+        let mut key = std::mem::ManuallyDrop::new(AeKey::random(&mut OsRng));
+        assert_ne!(key.0, [0_u8; 16]);
+
+        // Run the destructor in place so that the key bytes can be inspected afterwards. The
+        // storage is still owned by the `ManuallyDrop` wrapper and the key bytes have no validity
+        // invariants, so this is a best-effort check that dropping the key clears it.
+        unsafe { std::ptr::drop_in_place(&mut *key as *mut AeKey) };
+        assert_eq!(key.0, [0_u8; 16]);
+    }
+
+    #[test]
+    fn test_aes_decrypt_legacy_v0() {
+        let key = AeKey::random(&mut OsRng);
+
+        let ct = key.encrypt(55);
+        assert_eq!(key.decrypt_legacy_v0(&ct), key.decrypt(&ct));
+
+        let mut tampered_ct = ct;
+        tampered_ct.ciphertext[0] ^= 1;
+        assert_eq!(key.decrypt_legacy_v0(&tampered_ct), None);
+    }
+
+    #[test]
+    fn test_aes_decrypt_wrong_plaintext_length() {
+        let key = AeKey::random(&mut OsRng);
+        let nonce: Nonce = OsRng.gen::<[u8; 12]>();
+
+        // a valid ciphertext of a 9-byte plaintext should not be decrypted as a `u64` amount
+        let ciphertext = Aes128GcmSiv::new(&key.0.into())
+            .encrypt(&nonce.into(), [0_u8; 9].as_ref())
+            .unwrap();
+        assert_eq!(ciphertext.len(), 25);
+
+        assert!(AuthenticatedEncryption::decrypt_u64(&key, &nonce, &ciphertext).is_none());
+        assert_eq!(
+            AuthenticatedEncryption::try_decrypt_u64_with_aad(&key, &nonce, &ciphertext, &[]),
+            Err(DecryptError::MalformedCiphertext)
+        );
+    }
+
+    #[test]
+    fn test_aes_encrypt_traced() {
+        let key = AeKey::random(&mut OsRng);
+
+        let mut traced = vec![];
+        let ct_0 = key.encrypt_traced(55, NonceSource::Os, |source, nonce| {
+            traced.push((*source, *nonce))
+        });
+        let ct_1 = key.encrypt_traced(55, NonceSource::Os, |source, nonce| {
+            traced.push((*source, *nonce))
+        });
+        assert_eq!(traced[0], (NonceSource::Os, ct_0.nonce));
+        assert_eq!(traced[1], (NonceSource::Os, ct_1.nonce));
+        assert_ne!(ct_0.nonce, ct_1.nonce);
+
+        let nonce = [7_u8; 12];
+        let ct = key.encrypt_traced(55, NonceSource::Supplied(nonce), |source, traced_nonce| {
+            assert_eq!(*source, NonceSource::Supplied(nonce));
+            assert_eq!(*traced_nonce, nonce);
+        });
+        assert_eq!(ct.nonce, nonce);
+        assert_eq!(key.decrypt(&ct).unwrap(), 55);
+    }
+
+    #[test]
+    fn test_aes_decrypt_sum() {
+        let key = AeKey::random(&mut OsRng);
+
+        let cts = [key.encrypt(u64::MAX), key.encrypt(u64::MAX), key.encrypt(2)];
+        assert_eq!(key.decrypt_sum(&cts), Some(2 * u64::MAX as u128 + 2));
+
+        assert_eq!(key.decrypt_sum(&[]), Some(0));
+
+        let mut tampered_cts = cts;
+        tampered_cts[2].ciphertext[0] ^= 1;
+        assert_eq!(key.decrypt_sum(&tampered_cts), None);
+    }
+
+    #[test]
+    fn test_aes_encrypt_deterministic() {
+        let key = AeKey::random(&mut OsRng);
+
+        let ct_0 = key.encrypt_deterministic(55, 0);
+        let ct_1 = key.encrypt_deterministic(55, 0);
+        assert_eq!(ct_0.to_bytes(), ct_1.to_bytes());
+        assert_eq!(key.decrypt(&ct_0).unwrap(), 55);
+
+        let ct_2 = key.encrypt_deterministic(55, 1);
+        assert_ne!(ct_0.nonce, ct_2.nonce);
+        assert_eq!(key.decrypt(&ct_2).unwrap(), 55);
+    }
+
+    #[test]
+    fn test_aes_try_decrypt() {
+        let key = AeKey::random(&mut OsRng);
+
+        let mut ct = key.encrypt(55);
+        assert_eq!(ct.try_decrypt(&key), Ok(55));
+
+        // wrong key
+        let other_key = AeKey::random(&mut OsRng);
+        assert_eq!(
+            ct.try_decrypt(&other_key),
+            Err(DecryptError::AuthenticationFailed)
+        );
+
+        // tampered ciphertext
+        ct.ciphertext[0] ^= 1;
+        assert_eq!(
+            ct.try_decrypt(&key),
+            Err(DecryptError::AuthenticationFailed)
+        );
+    }
+
+    #[test]
+    fn test_aes_key_from_env() {
+        let key = AeKey::random(&mut OsRng);
+
+        let var_name = "SOLANA_ZK_TOKEN_SDK_TEST_AE_KEY";
+        std::env::set_var(var_name, BASE64_STANDARD.encode(key.0));
+        assert_eq!(AeKey::from_env(var_name).unwrap().0, key.0);
+
+        std::env::set_var(var_name, BASE64_STANDARD.encode([0_u8; 15]));
+        assert!(AeKey::from_env(var_name).is_err());
+
+        std::env::remove_var(var_name);
+        assert!(AeKey::from_env(var_name).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "argon2")]
+    fn test_aes_key_from_password() {
+        let password = "correct horse battery staple";
+
+        let key = AeKey::from_password(password, b"salt-one").unwrap();
+        assert_eq!(
+            key.0,
+            AeKey::from_password(password, b"salt-one").unwrap().0
+        );
+        assert_ne!(
+            key.0,
+            AeKey::from_password(password, b"salt-two").unwrap().0
+        );
+
+        assert!(AeKey::from_password(password, b"short").is_err());
+    }
+
+    #[test]
+    fn test_aes_encrypt_available_balance() {
+        let key = AeKey::random(&mut OsRng);
+
+        let decryptable_available_balance = key.encrypt_available_balance(55);
+        assert_eq!(key.decrypt(&decryptable_available_balance).unwrap(), 55);
+    }
+
+    #[test]
+    fn test_aes_with_nonce() {
+        let key = AeKey::random(&mut OsRng);
+        let ct = key.encrypt(55_u64);
+
+        let same_nonce = ct.with_nonce(ct.nonce);
+        assert_eq!(key.decrypt(&same_nonce), Some(55_u64));
+
+        let mut nonce = ct.nonce;
+        nonce[0] ^= 1;
+        let swapped = ct.with_nonce(nonce);
+        assert_eq!(swapped.ciphertext, ct.ciphertext);
+        assert_eq!(key.decrypt(&swapped), None);
+        assert!(swapped.try_decrypt(&key).is_err());
+
+        // two ciphertexts of different amounts under the same nonce
+        let other = key.encrypt(77_u64).with_nonce(ct.nonce);
+        assert_eq!(key.decrypt(&other), None);
+    }
+
+    #[test]
+    fn test_aes_encrypt_with_aad() {
+        let key = AeKey::random(&mut OsRng);
+
+        let ct = key.encrypt_with_aad(55_u64, b"context");
+        assert_eq!(key.decrypt_with_aad(&ct, b"context"), Some(55_u64));
+        assert_eq!(key.decrypt_with_aad(&ct, b"other"), None);
+        assert_eq!(key.decrypt(&ct), None);
+
+        // encryption with an empty associated data is compatible with `decrypt`
+        let ct = key.encrypt_with_aad(55_u64, &[]);
+        assert_eq!(key.decrypt(&ct), Some(55_u64));
+    }
+
+    #[test]
+    fn test_aes_encrypt_with_expiry() {
+        let key = AeKey::random(&mut OsRng);
+        let expires_at = 1_700_000_000_i64;
+        let ct = key.encrypt_with_expiry(55_u64, expires_at);
+
+        assert_eq!(
+            key.decrypt_with_expiry(&ct, expires_at, expires_at - 1),
+            Some(55_u64)
+        );
+        assert_eq!(
+            key.decrypt_with_expiry(&ct, expires_at, expires_at),
+            Some(55_u64)
+        );
+
+        // expired
+        assert_eq!(
+            key.decrypt_with_expiry(&ct, expires_at, expires_at + 1),
+            None
+        );
+
+        // expiry that does not match the associated data
+        assert_eq!(
+            key.decrypt_with_expiry(&ct, expires_at + 100, expires_at),
+            None
+        );
+    }
+
+    #[test]
+    fn test_aes_from_seed_checked() {
+        assert!(AeKey::from_seed_checked(&[0; 32]).is_err());
+        assert!(AeKey::from_seed_checked(&[1, 2, 3, 4].repeat(8)).is_err());
+
+        let seed = OsRng.gen::<[u8; 32]>();
+        let key = AeKey::from_seed_checked(&seed).unwrap();
+        assert_eq!(key.0, AeKey::from_seed(&seed).unwrap().0);
+
+        // the length check of `from_seed` still applies
+        assert!(AeKey::from_seed_checked(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9]).is_err());
+    }
+
+    #[test]
+    fn test_aes_new_shared() {
+        let keypair = Keypair::new();
+        let expected_key_bytes: Vec<_> = (0..4)
+            .map(|_| Pubkey::new_unique())
+            .map(|address| (address, AeKey::new(&keypair, &address).unwrap().0))
+            .collect();
+
+        let signer: Arc<dyn Signer + Send + Sync> = Arc::new(keypair);
+        let handles: Vec<_> = expected_key_bytes
+            .iter()
+            .map(|(address, _)| {
+                let signer = Arc::clone(&signer);
+                let address = *address;
+                std::thread::spawn(move || AeKey::new_shared(signer, &address).unwrap().0)
+            })
+            .collect();
+
+        let key_bytes: Vec<_> = handles
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        for (key_bytes, (_, expected_key_bytes)) in key_bytes.iter().zip(&expected_key_bytes) {
+            assert_eq!(key_bytes, expected_key_bytes);
+        }
+        assert_ne!(key_bytes[0], key_bytes[1]);
+    }
+
+    #[test]
+    fn test_aes_can_decrypt() {
+        let key = AeKey::random(&mut OsRng);
+        let mut ct = key.encrypt(55_u64);
+
+        assert!(key.can_decrypt(&ct));
+        assert!(!AeKey::random(&mut OsRng).can_decrypt(&ct));
+
+        ct.ciphertext[0] ^= 1;
+        assert!(!key.can_decrypt(&ct));
+    }
+
+    #[test]
+    fn test_aes_key_armored() {
+        let key = AeKey::random(&mut OsRng);
+        let armored = key.to_armored();
+        assert!(armored.starts_with("-----BEGIN AE KEY-----\n"));
+        assert!(armored.ends_with("-----END AE KEY-----\n"));
+        assert_eq!(AeKey::from_armored(&armored).unwrap().0, key.0);
+
+        let malformed = armored.replace("BEGIN AE KEY", "BEGIN KEY");
+        assert!(AeKey::from_armored(&malformed).is_none());
+
+        let truncated = armored.replace("-----END AE KEY-----", "");
+        assert!(AeKey::from_armored(&truncated).is_none());
+
+        let wrong_length = format!(
+            "-----BEGIN AE KEY-----\n{}\n-----END AE KEY-----\n",
+            BASE64_STANDARD.encode([0_u8; 32])
+        );
+        assert!(AeKey::from_armored(&wrong_length).is_none());
+    }
+
+    #[test]
+    fn test_aes_decrypt_mmap() {
+        let key = AeKey::random(&mut OsRng);
+
+        let mut tampered_ct = key.encrypt(1_u64);
+        tampered_ct.ciphertext[0] ^= 1;
+
+        let mut data = vec![];
+        data.extend_from_slice(&key.encrypt(0_u64).to_bytes());
+        data.extend_from_slice(&tampered_ct.to_bytes());
+        data.extend_from_slice(&key.encrypt(2_u64).to_bytes());
+
+        let amounts: Vec<_> = key.decrypt_mmap(&data).unwrap().collect();
+        assert_eq!(amounts, vec![Some(0_u64), None, Some(2_u64)]);
+
+        assert!(key.decrypt_mmap(&[]).unwrap().next().is_none());
+        assert_eq!(
+            key.decrypt_mmap(&data[..40]).err(),
+            Some(AuthenticatedEncryptionError::InvalidCiphertextDataLength)
+        );
+    }
+
+    #[test]
+    fn test_aes_parse_recoverable() {
+        let key = AeKey::random(&mut OsRng);
+        let cts = [key.encrypt(0_u64), key.encrypt(1_u64)];
+
+        let mut bytes = AeCiphertext::serialize_many(&cts);
+        let (parsed_cts, invalid_offsets) = AeCiphertext::parse_recoverable(&bytes);
+        assert_eq!(parsed_cts.len(), 2);
+        assert!(invalid_offsets.is_empty());
+
+        // trailing partial record
+        bytes.extend_from_slice(&[0_u8; 10]);
+        let (parsed_cts, invalid_offsets) = AeCiphertext::parse_recoverable(&bytes);
+        assert_eq!(invalid_offsets, vec![72]);
+        let amounts: Vec<_> = parsed_cts.iter().map(|ct| key.decrypt(ct)).collect();
+        assert_eq!(amounts, vec![Some(0_u64), Some(1_u64)]);
+    }
+
+    #[test]
+    fn test_aes_conditional_select() {
+        let key = AeKey::random(&mut OsRng);
+        let ct_a = key.encrypt(55_u64);
+        let ct_b = key.encrypt(77_u64);
+
+        let selected = AeCiphertext::conditional_select(&ct_a, &ct_b, Choice::from(0));
+        assert_eq!(selected.to_bytes(), ct_a.to_bytes());
+        assert_eq!(key.decrypt(&selected), Some(55_u64));
+
+        let selected = AeCiphertext::conditional_select(&ct_a, &ct_b, Choice::from(1));
+        assert_eq!(selected.to_bytes(), ct_b.to_bytes());
+        assert_eq!(key.decrypt(&selected), Some(77_u64));
+    }
+
+    #[test]
+    fn test_aes_is_deterministic_for() {
+        let key = AeKey::random(&mut OsRng);
+
+        let ct = key.encrypt_deterministic(55_u64, 1);
+        assert!(key.is_deterministic_for(&ct, 55_u64, 1));
+        assert!(!key.is_deterministic_for(&ct, 55_u64, 2));
+        assert!(!key.is_deterministic_for(&ct, 77_u64, 1));
+
+        let ct = key.encrypt(55_u64);
+        assert!(!key.is_deterministic_for(&ct, 55_u64, 1));
+    }
+
+    #[test]
+    fn test_aes_key_solana_format() {
+        let key = AeKey::random(&mut OsRng);
+        let mut file = vec![];
+        key.write_as_solana_format(&mut file).unwrap();
+
+        let read_key = AeKey::read_from_solana_format(&mut file.as_slice()).unwrap();
+        assert_eq!(read_key.0, key.0);
+
+        // Solana tools do not accept the file as a signing keypair
+        assert!(Keypair::read(&mut file.as_slice()).is_err());
+
+        // a signing keypair file is not accepted as an `AeKey` file
+        let mut keypair_file = vec![];
+        Keypair::new().write(&mut keypair_file).unwrap();
+        assert!(AeKey::read_from_solana_format(&mut keypair_file.as_slice()).is_err());
+
+        // the plain `EncodableKey` format of `AeKey` is not accepted either
+        let mut plain_file = vec![];
+        key.write(&mut plain_file).unwrap();
+        assert!(AeKey::read_from_solana_format(&mut plain_file.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_ae_test_vectors() {
+        for (key, amount, nonce, expected_ct) in ae_test_vectors() {
+            let ct = AuthenticatedEncryption::encrypt_with_nonce(&key, amount, nonce);
+            assert_eq!(ct.to_bytes(), expected_ct.to_bytes());
+            assert_eq!(key.decrypt(&expected_ct), Some(amount));
+        }
+    }
+
+    #[test]
+    fn test_aes_new_key_bytes() {
+        let keypair = Keypair::new();
+        let address = Pubkey::new_unique();
+
+        let message = Message::new(
+            &[Instruction::new_with_bytes(address, b"AeKey", vec![])],
+            Some(&keypair.pubkey()),
+        );
+        let signature = keypair.sign_message(&message.serialize());
+
+        let key = AeKey::new(&keypair, &address).unwrap();
+        assert_eq!(&key.0[..], &signature.as_ref()[..16]);
+    }
+
+    #[test]
+    fn test_aes_decrypt_bounded() {
+        let key = AeKey::random(&mut OsRng);
+
+        let ct = key.encrypt(1_000_u64);
+        assert_eq!(key.decrypt_bounded(&ct, 16), Some(1_000_u64));
+
+        let ct = key.encrypt(70_000_u64);
+        assert_eq!(key.decrypt_bounded(&ct, 16), None);
+        assert_eq!(key.decrypt_bounded(&ct, 48), Some(70_000_u64));
+
+        let ct = key.encrypt(u64::MAX);
+        assert_eq!(key.decrypt_bounded(&ct, 64), Some(u64::MAX));
+        assert_eq!(key.decrypt_bounded(&ct, 0), None);
+    }
+
+    #[test]
+    fn test_aes_mac() {
+        let key = AeKey((0..16).collect::<Vec<u8>>().try_into().unwrap());
+        let tag = key.mac(b"metadata");
+        assert_eq!(
+            tag,
+            [
+                141, 124, 88, 115, 189, 38, 90, 92, 210, 113, 156, 201, 35, 80, 80, 237, 174, 134,
+                82, 157, 90, 134, 117, 224, 104, 184, 231, 190, 142, 37, 77, 168
+            ]
+        );
+        assert!(key.verify_mac(b"metadata", &tag));
+
+        // tampered data
+        assert!(!key.verify_mac(b"metadatA", &tag));
+
+        // tampered tag
+        let mut tampered_tag = tag;
+        tampered_tag[0] ^= 1;
+        assert!(!key.verify_mac(b"metadata", &tampered_tag));
+
+        // wrong key
+        assert!(!AeKey::random(&mut OsRng).verify_mac(b"metadata", &tag));
+    }
+
+    #[test]
+    fn test_aes_encrypt_batch() {
+        let key = AeKey::random(&mut OsRng);
+        let amounts: Vec<u64> = (0..16).collect();
+
+        let cts = key.encrypt_batch(&amounts);
+        let decrypted: Vec<_> = cts.iter().map(|ct| key.decrypt(ct).unwrap()).collect();
+        assert_eq!(decrypted, amounts);
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn test_aes_encrypt_batch_par() {
+        let key = AeKey::random(&mut OsRng);
+        let amounts: Vec<u64> = (0..1024).collect();
+
+        let cts = key.encrypt_batch_par(&amounts);
+        let decrypted: Vec<_> = cts.iter().map(|ct| key.decrypt(ct).unwrap()).collect();
+        assert_eq!(decrypted, amounts);
+    }
+
+    #[test]
+    fn test_aes_encrypt_split() {
+        let key = AeKey::random(&mut OsRng);
+
+        let cts = key.encrypt_split(55, 3, &mut OsRng);
+        assert_eq!(cts.len(), 3);
+        assert_eq!(key.decrypt_join(&cts), Some(55));
+
+        let cts = key.encrypt_split(0, 3, &mut OsRng);
+        assert_eq!(cts.len(), 3);
+        assert!(cts.iter().all(|ct| key.decrypt(ct) == Some(0)));
+        assert_eq!(key.decrypt_join(&cts), Some(0));
+
+        let cts = key.encrypt_split(u64::MAX, 5, &mut OsRng);
+        assert_eq!(key.decrypt_join(&cts), Some(u64::MAX));
+
+        let other_key = AeKey::random(&mut OsRng);
+        assert_eq!(other_key.decrypt_join(&cts), None);
+    }
+
+    #[test]
+    fn test_aes_ciphertext_display() {
+        let key = AeKey::random(&mut OsRng);
+        let ct = key.encrypt(55);
+
+        let display = ct.to_display();
+        assert_eq!(display.nonce_base64, BASE64_STANDARD.encode(ct.nonce));
+        assert_eq!(
+            display.ciphertext_base64,
+            BASE64_STANDARD.encode(ct.ciphertext)
+        );
+
+        let decoded = AeCiphertext::from_display(&display).unwrap();
+        assert_eq!(decoded.to_bytes(), ct.to_bytes());
+        assert_eq!(key.decrypt(&decoded), Some(55));
+
+        let swapped = AeCiphertextDisplay {
+            nonce_base64: display.ciphertext_base64.clone(),
+            ciphertext_base64: display.nonce_base64.clone(),
+        };
+        assert!(AeCiphertext::from_display(&swapped).is_none());
+
+        let invalid = AeCiphertextDisplay {
+            nonce_base64: "not base64!".to_string(),
+            ciphertext_base64: display.ciphertext_base64,
+        };
+        assert!(AeCiphertext::from_display(&invalid).is_none());
+    }
+
+    #[test]
+    fn test_aes_from_transaction_signature() {
+        let signature = Signature::new(&[1_u8; 64]);
+        let other_signature = Signature::new(&[2_u8; 64]);
+
+        let key = AeKey::from_transaction_signature(&signature, b"context").unwrap();
+        let same_key = AeKey::from_transaction_signature(&signature, b"context").unwrap();
+        let other_key = AeKey::from_transaction_signature(&other_signature, b"context").unwrap();
+        let other_context_key =
+            AeKey::from_transaction_signature(&signature, b"other context").unwrap();
+
+        assert_eq!(key.0, same_key.0);
+        assert_ne!(key.0, other_key.0);
+        assert_ne!(key.0, other_context_key.0);
+
+        assert_eq!(
+            AeKey::from_transaction_signature(&Signature::default(), b"context").unwrap_err(),
+            AuthenticatedEncryptionError::DefaultSignature
+        );
+    }
+
+    #[test]
+    fn test_aes_ciphertext_merkle_root() {
+        let cts: Vec<AeCiphertext> = (0..3_u8)
+            .map(|i| AeCiphertext {
+                nonce: [i; 12],
+                ciphertext: [i; 24],
+            })
+            .collect();
+        let leaves: Vec<[u8; 32]> = cts
+            .iter()
+            .map(|ct| Sha3_256::digest(&ct.to_bytes()).into())
+            .collect();
+        let node = |left: &[u8; 32], right: &[u8; 32]| -> [u8; 32] {
+            let mut hasher = Sha3_256::new();
+            hasher.update(left);
+            hasher.update(right);
+            hasher.finalize().into()
+        };
+
+        assert_eq!(AeCiphertext::merkle_root(&[]), [0; 32]);
+        assert_eq!(AeCiphertext::merkle_root(&cts[..1]), leaves[0]);
+        assert_eq!(
+            AeCiphertext::merkle_root(&cts[..2]),
+            node(&leaves[0], &leaves[1])
+        );
+        assert_eq!(
+            AeCiphertext::merkle_root(&cts),
+            node(&node(&leaves[0], &leaves[1]), &node(&leaves[2], &leaves[2]))
+        );
+
+        let mut reordered = cts.clone();
+        reordered.swap(0, 1);
+        assert_ne!(
+            AeCiphertext::merkle_root(&reordered),
+            AeCiphertext::merkle_root(&cts)
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_aes_tracing_spans() {
+        use {
+            std::sync::{
+                atomic::{AtomicU64, Ordering},
+                Mutex,
+            },
+            tracing::{
+                field::{Field, Visit},
+                span::{Attributes, Id, Record},
+                Event, Metadata, Subscriber,
+            },
+        };
+
+        // The name of a span together with the names of its fields
+        type SpanFields = (&'static str, Vec<&'static str>);
+
+        // Records the name of each span, the names of its fields, and the recorded values
+        #[derive(Clone, Default)]
+        struct SpanRecorder {
+            next_id: Arc<AtomicU64>,
+            spans: Arc<Mutex<Vec<SpanFields>>>,
+            values: Arc<Mutex<Vec<(&'static str, String)>>>,
+        }
+
+        struct ValueVisitor<'a>(&'a Mutex<Vec<(&'static str, String)>>);
+        impl Visit for ValueVisitor<'_> {
+            fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((field.name(), format!("{value:?}")));
+            }
+        }
+
+        impl Subscriber for SpanRecorder {
+            fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+                true
+            }
+            fn new_span(&self, span: &Attributes<'_>) -> Id {
+                let fields = span.metadata().fields().iter().map(|f| f.name()).collect();
+                self.spans
+                    .lock()
+                    .unwrap()
+                    .push((span.metadata().name(), fields));
+                span.record(&mut ValueVisitor(&self.values));
+                Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+            }
+            fn record(&self, _span: &Id, values: &Record<'_>) {
+                values.record(&mut ValueVisitor(&self.values));
+            }
+            fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+            fn event(&self, _event: &Event<'_>) {}
+            fn enter(&self, _span: &Id) {}
+            fn exit(&self, _span: &Id) {}
+        }
+
+        let recorder = SpanRecorder::default();
+        let key = AeKey::random(&mut OsRng);
+        let other_key = AeKey::random(&mut OsRng);
+        let amount = 987_654_321_u64;
+
+        let versioned_ct = key.encrypt_versioned(amount, 1);
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let ct = key.encrypt(amount);
+            assert_eq!(key.decrypt(&ct), Some(amount));
+            assert_eq!(other_key.decrypt(&ct), None);
+
+            // the other decryption paths are traced with the same span
+            assert_eq!(ct.decrypt(&key), Some(amount));
+            assert!(key.can_decrypt(&ct));
+            assert!(!other_key.can_decrypt(&ct));
+            assert_eq!(key.decrypt_versioned(&versioned_ct), Some((1, amount)));
+            let amounts: Vec<_> = key.decrypt_mmap(&ct.to_bytes()).unwrap().collect();
+            assert_eq!(amounts, vec![Some(amount)]);
+        });
+
+        let spans = recorder.spans.lock().unwrap();
+        let decrypt_span = ("ae_key_decrypt", vec!["success", "elapsed_us"]);
+        let mut expected_spans = vec![("ae_key_encrypt", vec!["elapsed_us"])];
+        expected_spans.extend(std::iter::repeat(decrypt_span).take(7));
+        assert_eq!(*spans, expected_spans);
+
+        let values = recorder.values.lock().unwrap();
+        let successes: Vec<&str> = values
+            .iter()
+            .filter(|(name, _)| *name == "success")
+            .map(|(_, value)| value.as_str())
+            .collect();
+        assert_eq!(
+            successes,
+            vec!["true", "false", "true", "true", "false", "true", "true"]
+        );
+        assert_eq!(
+            values
+                .iter()
+                .filter(|(name, _)| *name == "elapsed_us")
+                .count(),
+            8
+        );
+        assert!(values
+            .iter()
+            .all(|(_, value)| !value.contains(&amount.to_string())));
+    }
+
+    #[test]
+    fn test_aes_encrypt_versioned() {
+        let key = AeKey::random(&mut OsRng);
+
+        let ct = key.encrypt_versioned(55, 3);
+        assert_eq!(key.decrypt_versioned(&ct), Some((3, 55)));
+
+        let ct = key.encrypt_versioned(u64::MAX, u8::MAX);
+        assert_eq!(key.decrypt_versioned(&ct), Some((u8::MAX, u64::MAX)));
+
+        let other_key = AeKey::random(&mut OsRng);
+        assert_eq!(other_key.decrypt_versioned(&ct), None);
+
+        let mut tampered = ct;
+        tampered.ciphertext[0] ^= 1;
+        assert_eq!(key.decrypt_versioned(&tampered), None);
+    }
+
+    #[test]
+    fn test_aes_ciphertext_ct_eq_bytes() {
+        let key = AeKey::random(&mut OsRng);
+        let ct = key.encrypt(55);
+        let bytes = ct.to_bytes();
+
+        assert!(bool::from(ct.ct_eq_bytes(&bytes)));
+
+        let mut unequal = bytes;
+        unequal[35] ^= 1;
+        assert!(!bool::from(ct.ct_eq_bytes(&unequal)));
+
+        assert!(!bool::from(ct.ct_eq_bytes(&bytes[..35])));
+        assert!(!bool::from(ct.ct_eq_bytes(&[])));
+
+        let mut longer = bytes.to_vec();
+        longer.push(0);
+        assert!(!bool::from(ct.ct_eq_bytes(&longer)));
+    }
+
+    #[test]
+    fn test_aes_derive_viewing_key() {
+        let key = AeKey::random(&mut OsRng);
+        let viewing_key = key.derive_viewing_key();
+
+        assert_ne!(viewing_key.0, key.0);
+        assert_eq!(viewing_key.0, key.derive_viewing_key().0);
+        assert_ne!(
+            viewing_key.0,
+            AeKey::random(&mut OsRng).derive_viewing_key().0
+        );
+
+        // the viewing key cannot decrypt ciphertexts under the key until they are re-encrypted
+        let ct = key.encrypt(55);
+        assert_eq!(viewing_key.decrypt(&ct), None);
+        let reencrypted_ct = viewing_key.encrypt(key.decrypt(&ct).unwrap());
+        assert_eq!(viewing_key.decrypt(&reencrypted_ct), Some(55));
+    }
+
+    #[test]
+    fn test_aes_encrypt_with_epoch() {
+        let key = AeKey::random(&mut OsRng);
+
+        let ct = key.encrypt_with_epoch(55, 7);
+        assert_eq!(ct.epoch, 7);
+        assert_eq!(key.decrypt(&ct.ciphertext), Some(55));
+
+        let decoded = EpochCiphertext::from_bytes(&ct.to_bytes()).unwrap();
+        assert_eq!(decoded.epoch, 7);
+        assert_eq!(decoded.ciphertext.to_bytes(), ct.ciphertext.to_bytes());
+        assert!(EpochCiphertext::from_bytes(&ct.to_bytes()[..39]).is_none());
+    }
+
+    #[test]
+    fn test_aes_new() {
+        let keypair1 = Keypair::new();
+        let keypair2 = Keypair::new();
+
+        assert_ne!(
+            AeKey::new(&keypair1, &Pubkey::default()).unwrap().0,
+            AeKey::new(&keypair2, &Pubkey::default()).unwrap().0,
+        );
+
+        let null_signer = NullSigner::new(&Pubkey::default());
+        assert!(AeKey::new(&null_signer, &Pubkey::default()).is_err());
+    }
+
+    #[test]
+    fn test_aes_ciphertext_bincode() {
+        let key = AeKey::random(&mut OsRng);
+        let ct = key.encrypt(55);
+
+        let encoded = bincode::serialize(&ct).unwrap();
+        assert_eq!(encoded.len(), 36);
+        assert_eq!(encoded, ct.to_bytes());
+
+        let decoded: AeCiphertext = bincode::deserialize(&encoded).unwrap();
+        assert_eq!(decoded.to_bytes(), ct.to_bytes());
+    }
+
+    #[test]
+    fn test_aes_ciphertext_serialize_many() {
+        let key = AeKey::random(&mut OsRng);
+        let cts = [key.encrypt(10), key.encrypt(20), key.encrypt(30)];
+
+        let bytes = AeCiphertext::serialize_many(&cts);
+        assert_eq!(bytes.len(), 36 * cts.len());
+        assert_eq!(bytes.capacity(), 36 * cts.len());
+
+        let decoded = AeCiphertext::deserialize_many(&bytes).unwrap();
+        assert_eq!(decoded.len(), cts.len());
+        for (decoded_ct, ct) in decoded.iter().zip(cts.iter()) {
+            assert_eq!(decoded_ct.to_bytes(), ct.to_bytes());
+        }
+
+        assert!(AeCiphertext::deserialize_many(&bytes[..35]).is_none());
+        assert_eq!(AeCiphertext::deserialize_many(&[]).unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_aes_ciphertext_base64_url() {
+        let key = AeKey::random(&mut OsRng);
+
+        // the standard alphabet encoding of these bytes contains both `+` and `/`
+        let ct = AeCiphertext {
+            nonce: [0xfb; 12],
+            ciphertext: [0xff; 24],
+        };
+        assert!(ct.to_string().contains('+'));
+        assert!(ct.to_string().contains('/'));
+
+        let encoded = ct.to_base64_url();
+        assert!(!encoded.contains('+'));
+        assert!(!encoded.contains('/'));
+        assert_eq!(
+            AeCiphertext::from_base64_url(&encoded).unwrap().to_bytes(),
+            ct.to_bytes()
+        );
+
+        let ct = key.encrypt(55);
+        let decoded = AeCiphertext::from_base64_url(&ct.to_base64_url()).unwrap();
+        assert_eq!(decoded.decrypt(&key).unwrap(), 55);
+
+        assert!(AeCiphertext::from_base64_url(&ct.to_string()[..10]).is_none());
     }
 }
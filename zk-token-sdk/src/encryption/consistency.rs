@@ -0,0 +1,74 @@
+//! Consistency check between the authenticated encryption and the ElGamal encryption of an amount.
+//!
+//! The available balance of a confidential token account is stored both as an ElGamal ciphertext
+//! and as an authenticated encryption ciphertext, and the two ciphertexts must encrypt the same
+//! amount.
+
+use {
+    crate::encryption::{
+        auth_encryption::{AeCiphertext, AeKey},
+        elgamal::{ElGamalCiphertext, ElGamalSecretKey},
+    },
+    curve25519_dalek::traits::IsIdentity,
+};
+
+/// Check that an authenticated encryption ciphertext and an ElGamal ciphertext encrypt the same
+/// amount.
+///
+/// The authenticated encryption ciphertext is decrypted first. Instead of solving the discrete log
+/// for the ElGamal ciphertext, the decrypted amount is subtracted from the ElGamal ciphertext and
+/// the result is checked to be an encryption of zero. This works for any `u64` amount.
+///
+/// Returns `false` if the authenticated encryption ciphertext fails to decrypt.
+pub fn verify_ae_elgamal_consistency(
+    ae_key: &AeKey,
+    ae_ct: &AeCiphertext,
+    elgamal_secret: &ElGamalSecretKey,
+    elgamal_ct: &ElGamalCiphertext,
+) -> bool {
+    let Some(amount) = ae_key.decrypt(ae_ct) else {
+        return false;
+    };
+
+    let ciphertext = elgamal_ct.subtract_amount(amount);
+    let message_point = ciphertext.commitment.get_point()
+        - &(elgamal_secret.get_scalar() * ciphertext.handle.get_point());
+    message_point.is_identity()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::encryption::elgamal::ElGamalKeypair, rand::rngs::OsRng};
+
+    #[test]
+    fn test_verify_ae_elgamal_consistency() {
+        let ae_key = AeKey::random(&mut OsRng);
+        let elgamal_keypair = ElGamalKeypair::new_rand();
+
+        let amount = u64::MAX - 55;
+        let ae_ct = ae_key.encrypt(amount);
+        let elgamal_ct = elgamal_keypair.public.encrypt(amount);
+        assert!(verify_ae_elgamal_consistency(
+            &ae_key,
+            &ae_ct,
+            &elgamal_keypair.secret,
+            &elgamal_ct
+        ));
+
+        let other_elgamal_ct = elgamal_keypair.public.encrypt(amount - 1);
+        assert!(!verify_ae_elgamal_consistency(
+            &ae_key,
+            &ae_ct,
+            &elgamal_keypair.secret,
+            &other_elgamal_ct
+        ));
+
+        let other_ae_key = AeKey::random(&mut OsRng);
+        assert!(!verify_ae_elgamal_consistency(
+            &other_ae_key,
+            &ae_ct,
+            &elgamal_keypair.secret,
+            &elgamal_ct
+        ));
+    }
+}
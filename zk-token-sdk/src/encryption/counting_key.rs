@@ -0,0 +1,72 @@
+//! Authenticated encryption with counter-derived nonces.
+
+use {
+    crate::encryption::auth_encryption::{AeCiphertext, AeKey, AuthenticatedEncryption},
+    rand::{rngs::OsRng, Rng},
+    std::sync::atomic::{AtomicU64, Ordering},
+};
+
+/// An `AeKey` that derives the nonce of each encryption from a counter instead of an RNG.
+///
+/// The nonce is a random 4-byte prefix that is sampled when the key is wrapped followed by the
+/// big-endian encoding of the counter. Nonces are unique as long as a single `CountingAeKey`
+/// encrypts under the key. The counter space allows for `u64::MAX` encryptions; after that,
+/// `encrypt` returns `None` instead of wrapping around and reusing a nonce.
+pub struct CountingAeKey {
+    key: AeKey,
+    prefix: [u8; 4],
+    counter: AtomicU64,
+}
+impl CountingAeKey {
+    pub fn new(key: AeKey) -> Self {
+        Self {
+            key,
+            prefix: OsRng.gen::<[u8; 4]>(),
+            counter: AtomicU64::new(0),
+        }
+    }
+
+    /// Encrypt an amount with the next nonce, returning `None` if the counter is exhausted.
+    pub fn encrypt(&self, amount: u64) -> Option<AeCiphertext> {
+        let counter = self
+            .counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |counter| {
+                counter.checked_add(1)
+            })
+            .ok()?;
+
+        let mut nonce = [0_u8; 12];
+        nonce[..4].copy_from_slice(&self.prefix);
+        nonce[4..].copy_from_slice(&counter.to_be_bytes());
+        Some(AuthenticatedEncryption::encrypt_with_nonce(
+            &self.key, amount, nonce,
+        ))
+    }
+
+    pub fn decrypt(&self, ct: &AeCiphertext) -> Option<u64> {
+        self.key.decrypt(ct)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counting_ae_key() {
+        let key = CountingAeKey::new(AeKey::random(&mut OsRng));
+
+        let cts: Vec<_> = (0..4).map(|amount| key.encrypt(amount).unwrap()).collect();
+        for (amount, ct) in cts.iter().enumerate() {
+            assert_eq!(key.decrypt(ct), Some(amount as u64));
+        }
+        for pair in cts.windows(2) {
+            assert_eq!(pair[0].nonce[..4], pair[1].nonce[..4]);
+            assert!(pair[0].nonce < pair[1].nonce);
+        }
+
+        // the counter does not wrap around
+        key.counter.store(u64::MAX, Ordering::SeqCst);
+        assert!(key.encrypt(0).is_none());
+    }
+}
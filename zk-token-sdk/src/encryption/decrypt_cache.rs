@@ -0,0 +1,130 @@
+//! A least-recently-used cache of amounts that are decrypted with an authenticated encryption key.
+
+use {
+    crate::encryption::auth_encryption::{AeCiphertext, AeKey},
+    lru::LruCache,
+    zeroize::Zeroize,
+};
+
+/// An `AeKey` together with a least-recently-used cache of decrypted amounts.
+///
+/// Only ciphertexts that decrypt successfully are cached. The cached amounts are zeroized when they
+/// are evicted and when the cache is dropped.
+pub struct DecryptCache {
+    key: AeKey,
+    cache: LruCache<[u8; 36], u64>,
+}
+impl DecryptCache {
+    /// Create a cache that holds at most `capacity` decrypted amounts.
+    pub fn new(key: AeKey, capacity: usize) -> Self {
+        Self {
+            key,
+            cache: LruCache::new(capacity),
+        }
+    }
+
+    /// Return the cached amount of a ciphertext, decrypting and caching it on a miss.
+    pub fn get_or_decrypt(&mut self, ciphertext: &AeCiphertext) -> Option<u64> {
+        let bytes = ciphertext.to_bytes();
+        if let Some(amount) = self.cache.get(&bytes) {
+            return Some(*amount);
+        }
+
+        let amount = self.key.decrypt(ciphertext)?;
+        self.insert(bytes, amount);
+        Some(amount)
+    }
+
+    /// Cache an amount, zeroizing the amount of the least recently used entry in place before it is
+    /// evicted.
+    fn insert(&mut self, bytes: [u8; 36], amount: u64) {
+        if self.cache.len() == self.cache.cap() {
+            if let Some(lru_bytes) = self.cache.peek_lru().map(|(bytes, _)| *bytes) {
+                if let Some(lru_amount) = self.cache.peek_mut(&lru_bytes) {
+                    lru_amount.zeroize();
+                }
+                self.cache.pop_lru();
+            }
+        }
+        self.cache.put(bytes, amount);
+    }
+
+    /// Check whether the decrypted amount of a ciphertext is cached.
+    pub fn contains(&self, ciphertext: &AeCiphertext) -> bool {
+        self.cache.contains(&ciphertext.to_bytes())
+    }
+
+    /// The number of cached amounts.
+    pub fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
+
+impl Drop for DecryptCache {
+    fn drop(&mut self) {
+        for (_, amount) in self.cache.iter_mut() {
+            amount.zeroize();
+        }
+        self.cache.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rand::rngs::OsRng};
+
+    #[test]
+    fn test_decrypt_cache() {
+        let key = AeKey::random(&mut OsRng);
+        let ct_0 = key.encrypt(0_u64);
+        let ct_1 = key.encrypt(1_u64);
+        let ct_2 = key.encrypt(2_u64);
+        let mut cache = DecryptCache::new(key, 2);
+        assert!(cache.is_empty());
+
+        // cache hit
+        assert_eq!(cache.get_or_decrypt(&ct_0), Some(0_u64));
+        assert!(cache.contains(&ct_0));
+        assert_eq!(cache.get_or_decrypt(&ct_0), Some(0_u64));
+        assert_eq!(cache.len(), 1);
+
+        // a ciphertext that fails to decrypt is not cached
+        let other_ct = AeKey::random(&mut OsRng).encrypt(3_u64);
+        assert_eq!(cache.get_or_decrypt(&other_ct), None);
+        assert!(!cache.contains(&other_ct));
+        assert_eq!(cache.len(), 1);
+
+        // the least recently used amount is evicted at capacity
+        assert_eq!(cache.get_or_decrypt(&ct_1), Some(1_u64));
+        assert_eq!(cache.get_or_decrypt(&ct_2), Some(2_u64));
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(&ct_0));
+        assert!(cache.contains(&ct_1));
+        assert!(cache.contains(&ct_2));
+    }
+
+    #[test]
+    fn test_decrypt_cache_zeroizes_evicted_amounts() {
+        let key = AeKey::random(&mut OsRng);
+        let cts: Vec<AeCiphertext> = (1..=3_u64).map(|amount| key.encrypt(amount)).collect();
+        let mut cache = DecryptCache::new(key, 2);
+
+        assert_eq!(cache.get_or_decrypt(&cts[0]), Some(1));
+        assert_eq!(cache.get_or_decrypt(&cts[1]), Some(2));
+
+        // filling past capacity evicts the least recently used amount
+        assert_eq!(cache.get_or_decrypt(&cts[2]), Some(3));
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(&cts[0]));
+
+        // the evicted amount is decrypted again rather than read back from the zeroized slot
+        assert_eq!(cache.get_or_decrypt(&cts[0]), Some(1));
+        assert!(!cache.contains(&cts[1]));
+        assert!(cache.contains(&cts[2]));
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -0,0 +1,160 @@
+//! Collections of authenticated encryption keys.
+
+use {
+    crate::encryption::auth_encryption::{AeCiphertext, AeKey, EpochCiphertext},
+    solana_sdk::signer::EncodableKey,
+    std::{collections::BTreeMap, error, path::Path},
+};
+
+/// A collection of keys that are loaded from the `.aekey` files of a directory.
+///
+/// The keys are zeroized when the keystore is dropped.
+pub struct AeKeystore {
+    keys: Vec<(String, AeKey)>,
+}
+impl AeKeystore {
+    /// Load all regular files with the `.aekey` extension in `dir`, ordered by file name.
+    ///
+    /// Returns an error if the name of a key file is not valid UTF-8.
+    pub fn open(dir: &Path) -> Result<Self, Box<dyn error::Error>> {
+        let mut keys = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if !path.is_file()
+                || path
+                    .extension()
+                    .map_or(true, |extension| extension != "aekey")
+            {
+                continue;
+            }
+            let file_name = path
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .ok_or_else(|| format!("Invalid key file name: {}", path.display()))?
+                .to_string();
+            keys.push((file_name, AeKey::read_from_file(&path)?));
+        }
+        keys.sort_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(Self { keys })
+    }
+
+    /// Decrypt a ciphertext with the first key that can decrypt it.
+    ///
+    /// Returns the file name of the key together with the decrypted amount.
+    pub fn decrypt(&self, ct: &AeCiphertext) -> Option<(String, u64)> {
+        self.keys
+            .iter()
+            .find_map(|(file_name, key)| Some((file_name.clone(), key.decrypt(ct)?)))
+    }
+
+    /// The number of keys in the keystore.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+/// A collection of keys indexed by their key rotation epoch.
+///
+/// The keys are zeroized when the keystore is dropped.
+#[derive(Default)]
+pub struct EpochKeystore {
+    keys: BTreeMap<u32, AeKey>,
+}
+impl EpochKeystore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the key of an epoch, returning the key that it replaces.
+    pub fn insert(&mut self, epoch: u32, key: AeKey) -> Option<AeKey> {
+        self.keys.insert(epoch, key)
+    }
+
+    /// Decrypt a ciphertext with the key of its epoch.
+    ///
+    /// Returns `None` if the keystore has no key for the epoch or if the ciphertext fails to
+    /// decrypt with that key. Keys of other epochs are not tried.
+    pub fn decrypt_epoch(&self, ct: &EpochCiphertext) -> Option<u64> {
+        self.keys.get(&ct.epoch)?.decrypt(&ct.ciphertext)
+    }
+
+    /// The number of keys in the keystore.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, rand::rngs::OsRng};
+
+    #[test]
+    fn test_aes_keystore() {
+        let dir = tempfile::tempdir().unwrap();
+        let key_0 = AeKey::random(&mut OsRng);
+        let key_1 = AeKey::random(&mut OsRng);
+        key_0.write_to_file(dir.path().join("key_0.aekey")).unwrap();
+        key_1.write_to_file(dir.path().join("key_1.aekey")).unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "not a key").unwrap();
+        std::fs::create_dir(dir.path().join("backup.aekey")).unwrap();
+
+        let keystore = AeKeystore::open(dir.path()).unwrap();
+        assert_eq!(keystore.len(), 2);
+
+        let ct = key_1.encrypt(55_u64);
+        assert_eq!(
+            keystore.decrypt(&ct),
+            Some(("key_1.aekey".to_string(), 55_u64))
+        );
+
+        let other_ct = AeKey::random(&mut OsRng).encrypt(55_u64);
+        assert_eq!(keystore.decrypt(&other_ct), None);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_aes_keystore_invalid_file_name() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        AeKey::random(&mut OsRng)
+            .write_to_file(dir.path().join(OsStr::from_bytes(b"key_\xff.aekey")))
+            .unwrap();
+
+        assert!(AeKeystore::open(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_epoch_keystore() {
+        let old_key = AeKey::random(&mut OsRng);
+        let new_key = AeKey::random(&mut OsRng);
+        let old_ct = old_key.encrypt_with_epoch(55, 1);
+        let new_ct = new_key.encrypt_with_epoch(77, 2);
+
+        let mut keystore = EpochKeystore::new();
+        assert!(keystore.is_empty());
+        assert!(keystore.insert(1, old_key).is_none());
+        assert!(keystore.insert(2, new_key).is_none());
+        assert_eq!(keystore.len(), 2);
+
+        assert_eq!(keystore.decrypt_epoch(&old_ct), Some(55));
+        assert_eq!(keystore.decrypt_epoch(&new_ct), Some(77));
+
+        // the epoch selects the key, so a mistagged ciphertext fails to decrypt
+        let mut mistagged_ct = new_ct.clone();
+        mistagged_ct.epoch = 1;
+        assert_eq!(keystore.decrypt_epoch(&mistagged_ct), None);
+
+        let mut unknown_epoch_ct = new_ct;
+        unknown_epoch_ct.epoch = 3;
+        assert_eq!(keystore.decrypt_epoch(&unknown_epoch_ct), None);
+    }
+}
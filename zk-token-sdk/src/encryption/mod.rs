@@ -10,7 +10,16 @@
 //! - Basic type-wrapper around the AES-GCM-SIV symmetric authenticated encryption scheme
 //! implemented by [aes-gcm-siv](https://docs.rs/aes-gcm-siv/latest/aes_gcm_siv/) crate.
 
+#[cfg(feature = "proving")]
+pub mod account_keys;
 pub mod auth_encryption;
+#[cfg(feature = "proving")]
+pub mod consistency;
+pub mod counting_key;
+pub mod decrypt_cache;
 pub mod discrete_log;
 pub mod elgamal;
+pub mod keystore;
 pub mod pedersen;
+#[cfg(feature = "proving")]
+pub mod sealed_amount;
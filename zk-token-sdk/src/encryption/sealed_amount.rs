@@ -0,0 +1,71 @@
+//! Sealed-box style encryption of amounts to an ElGamal public key.
+//!
+//! An amount is encrypted under a one-time `AeKey` that is derived from an ephemeral
+//! Diffie-Hellman exchange with the recipient ElGamal public key, so the sender and the recipient
+//! do not need a prior key exchange.
+
+use {
+    crate::encryption::{
+        auth_encryption::{AeCiphertext, AeKey},
+        elgamal::{DecryptHandle, ElGamalPubkey, ElGamalSecretKey},
+        pedersen::{PedersenOpening, H},
+    },
+    core::convert::TryInto,
+    curve25519_dalek::ristretto::RistrettoPoint,
+    sha3::{Digest, Sha3_512},
+};
+
+/// An amount encrypted to the owner of an ElGamal public key without a prior key exchange.
+///
+/// The amount is encrypted under a one-time `AeKey` that is derived from an ephemeral
+/// Diffie-Hellman exchange with the recipient ElGamal public key. The ephemeral public component
+/// is attached to the ciphertext as a decryption handle.
+#[derive(Clone, Debug)]
+pub struct SealedAmount {
+    pub handle: DecryptHandle,
+    pub ciphertext: AeCiphertext,
+}
+
+/// Encrypt an amount to the owner of an ElGamal public key.
+pub fn seal_amount(recipient_elgamal: &ElGamalPubkey, amount: u64) -> SealedAmount {
+    let opening = PedersenOpening::new_rand();
+    let handle = recipient_elgamal.decrypt_handle(&opening);
+
+    let key = sealing_key(&(opening.get_scalar() * &(*H)));
+    SealedAmount {
+        handle,
+        ciphertext: key.encrypt(amount),
+    }
+}
+
+/// Decrypt an amount that was sealed to the ElGamal public key of `recipient_secret`.
+pub fn unseal_amount(recipient_secret: &ElGamalSecretKey, sealed: &SealedAmount) -> Option<u64> {
+    let key = sealing_key(&(recipient_secret.get_scalar() * sealed.handle.get_point()));
+    key.decrypt(&sealed.ciphertext)
+}
+
+fn sealing_key(shared_point: &RistrettoPoint) -> AeKey {
+    let mut hasher = Sha3_512::new();
+    hasher.update(b"SealedAmount");
+    hasher.update(shared_point.compress().as_bytes());
+    let result = hasher.finalize();
+
+    AeKey(result[..16].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::encryption::elgamal::ElGamalKeypair};
+
+    #[test]
+    fn test_seal_unseal_amount() {
+        let recipient = ElGamalKeypair::new_rand();
+        let amount = 55;
+
+        let sealed = seal_amount(&recipient.public, amount);
+        assert_eq!(unseal_amount(&recipient.secret, &sealed).unwrap(), amount);
+
+        let other = ElGamalKeypair::new_rand();
+        assert!(unseal_amount(&other.secret, &sealed).is_none());
+    }
+}
@@ -29,6 +29,13 @@ pub use {
     zero_balance::{ZeroBalanceProofContext, ZeroBalanceProofData},
 };
 
+// The instruction data of a proof instruction is the discriminant byte followed by the in-memory
+// representation of the `Pod` proof data (see `ZkProofData`). The wire format is little-endian,
+// which is the byte order of all supported targets; decoding the in-memory representation on a
+// big-endian target would silently misinterpret the proof data.
+#[cfg(target_endian = "big")]
+compile_error!("the proof instruction data format assumes a little-endian target");
+
 #[derive(Clone, Copy, Debug, FromPrimitive, ToPrimitive, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ProofType {
@@ -42,9 +49,12 @@ pub enum ProofType {
     PubkeyValidity,
 }
 
-pub trait ZkProofData<T: Pod> {
+pub trait ZkProofData<T: Pod>: Sized {
     const PROOF_TYPE: ProofType;
 
+    /// The length in bytes of the encoded proof data
+    const SIZE: usize = std::mem::size_of::<Self>();
+
     fn context_data(&self) -> &T;
 
     #[cfg(not(target_os = "solana"))]
@@ -218,6 +218,18 @@ impl TransferData {
     }
 }
 
+impl TransferData {
+    /// The ciphertexts and public keys of the transfer, which are stored as the proof context.
+    pub fn ciphertexts(&self) -> &TransferProofContext {
+        &self.context
+    }
+
+    /// The zero-knowledge proofs of the transfer.
+    pub fn proof(&self) -> &TransferProof {
+        &self.proof
+    }
+}
+
 impl ZkProofData<TransferProofContext> for TransferData {
     const PROOF_TYPE: ProofType = ProofType::Transfer;
 
@@ -688,4 +700,45 @@ mod test {
             550000_u64,
         );
     }
+
+    #[test]
+    fn test_transfer_data_portions() {
+        let source_keypair = ElGamalKeypair::new_rand();
+        let dest_pk = ElGamalKeypair::new_rand().public;
+        let auditor_pk = ElGamalKeypair::new_rand().public;
+
+        let spendable_balance: u64 = 77;
+        let spendable_ciphertext = source_keypair.public.encrypt(spendable_balance);
+
+        let transfer_data = TransferData::new(
+            55,
+            (spendable_balance, &spendable_ciphertext),
+            &source_keypair,
+            (&dest_pk, &auditor_pk),
+        )
+        .unwrap();
+
+        let bytes = bytemuck::bytes_of(&transfer_data);
+        let decoded = bytemuck::try_from_bytes::<TransferData>(bytes).unwrap();
+
+        let ciphertexts = decoded.ciphertexts();
+        assert_eq!(
+            bytemuck::bytes_of(ciphertexts),
+            &bytes[..std::mem::size_of::<TransferProofContext>()]
+        );
+        assert_eq!(
+            ciphertexts.transfer_pubkeys.source_pubkey,
+            source_keypair.public.into()
+        );
+
+        let proof = decoded.proof();
+        assert_eq!(
+            bytemuck::bytes_of(proof),
+            &bytes[std::mem::size_of::<TransferProofContext>()..]
+        );
+        assert_eq!(
+            proof.new_source_commitment,
+            transfer_data.proof.new_source_commitment
+        );
+    }
 }
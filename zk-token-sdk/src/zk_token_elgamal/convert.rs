@@ -504,11 +504,32 @@ mod target_arch {}
 mod tests {
     use {
         super::*,
-        crate::{encryption::pedersen::Pedersen, range_proof::RangeProof},
+        crate::{
+            encryption::{
+                auth_encryption::{AeCiphertext, AeKey},
+                pedersen::Pedersen,
+            },
+            range_proof::RangeProof,
+        },
         merlin::Transcript,
+        rand::rngs::OsRng,
         std::convert::TryInto,
     };
 
+    #[test]
+    fn test_pod_ae_ciphertext_cast_to_bytes_and_back() {
+        let key = AeKey::random(&mut OsRng);
+        let ct = key.encrypt(55_u64);
+
+        let pod_ct: pod::AeCiphertext = ct.clone().into();
+        let bytes = bytemuck::bytes_of(&pod_ct);
+        assert_eq!(bytes, &ct.to_bytes()[..]);
+
+        let pod_ct = *bytemuck::from_bytes::<pod::AeCiphertext>(bytes);
+        let ct: AeCiphertext = pod_ct.try_into().unwrap();
+        assert_eq!(key.decrypt(&ct), Some(55_u64));
+    }
+
     #[test]
     fn test_pod_range_proof_64() {
         let (comm, open) = Pedersen::new(55_u64);
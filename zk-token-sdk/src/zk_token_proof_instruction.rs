@@ -1,15 +1,65 @@
 //! Instructions provided by the ZkToken Proof program
 pub use crate::instruction::*;
 use {
+    crate::{
+        zk_token_proof_program::{
+            VERIFY_CIPHERTEXT_CIPHERTEXT_EQUALITY_COMPUTE_UNITS,
+            VERIFY_PUBKEY_VALIDITY_COMPUTE_UNITS, VERIFY_TRANSFER_COMPUTE_UNITS,
+            VERIFY_TRANSFER_WITH_FEE_COMPUTE_UNITS, VERIFY_WITHDRAW_COMPUTE_UNITS,
+            VERIFY_ZERO_BALANCE_COMPUTE_UNITS,
+        },
+        zk_token_proof_state::{ProofContextState, ProofContextStateMeta},
+    },
     bytemuck::bytes_of,
     num_derive::{FromPrimitive, ToPrimitive},
     num_traits::{FromPrimitive, ToPrimitive},
     solana_program::{
         instruction::{AccountMeta, Instruction},
         pubkey::Pubkey,
+        rent::Rent,
+    },
+    std::{any::TypeId, mem::size_of},
+};
+#[cfg(not(target_os = "solana"))]
+use {
+    solana_sdk::{
+        address_lookup_table_account::AddressLookupTableAccount,
+        compute_budget::ComputeBudgetInstruction,
+        hash::Hash,
+        message::{v0, CompileError},
     },
+    std::io::{self, Read},
 };
 
+/// The maximum length of the instruction data of a proof instruction with no accounts such that a
+/// transaction consisting of only the proof instruction fits in a packet.
+///
+/// The size of a transaction with a single signature and no other instructions is the packet data
+/// size of 1232 bytes minus 170 bytes of overhead:
+///   - 65 bytes for the signature and its length prefix
+///   - 3 bytes for the message header
+///   - 65 bytes for the fee payer and program account keys and their length prefix
+///   - 32 bytes for the recent blockhash
+///   - 5 bytes for the instruction list length prefix, the program id index, the account list
+///     length prefix, and the instruction data length prefix
+pub const MAX_INLINE_INSTRUCTION_DATA_LEN: usize = 1062;
+
+/// The number of compute units that the compute budget program consumes to process a
+/// `SetComputeUnitLimit` instruction.
+pub const SET_COMPUTE_UNIT_LIMIT_COMPUTE_UNITS: u32 = 150;
+
+/// The number of compute units that `ProofInstruction::recommended_compute_units` adds to the
+/// exact cost of a transaction as a safety margin.
+pub const COMPUTE_UNIT_LIMIT_MARGIN: u32 = 1_000;
+
+/// The version of the proof packet format of `ProofInstruction::to_packet`.
+pub const PROOF_PACKET_VERSION: u8 = 1;
+
+/// The bit of the discriminant byte that marks compressed proof data in the format of
+/// `ProofInstruction::encode_compressed`.
+#[cfg(all(feature = "zstd", not(target_os = "solana")))]
+const COMPRESSED_DISCRIMINANT_FLAG: u8 = 0x80;
+
 #[derive(Clone, Copy, Debug, FromPrimitive, ToPrimitive, PartialEq, Eq)]
 #[repr(u8)]
 pub enum ProofInstruction {
@@ -140,6 +190,191 @@ pub struct ContextStateInfo<'a> {
     pub context_state_account: &'a Pubkey,
     pub context_state_authority: &'a Pubkey,
 }
+impl<'a> ContextStateInfo<'a> {
+    /// Check that the context state account and authority are distinct and not the default
+    /// pubkey.
+    ///
+    /// A context state account that is its own authority cannot sign to close itself and is
+    /// therefore locked.
+    pub fn validate(&self) -> Result<(), ContextStateError> {
+        if self.context_state_authority == &Pubkey::default() {
+            return Err(ContextStateError::DefaultAuthority);
+        }
+        if self.context_state_account == &Pubkey::default() {
+            return Err(ContextStateError::DefaultAccount);
+        }
+        if self.context_state_account == self.context_state_authority {
+            return Err(ContextStateError::AccountIsAuthority);
+        }
+        Ok(())
+    }
+
+    /// Check whether two context state infos reference the same context state account.
+    pub fn conflicts_with(&self, other: &ContextStateInfo) -> bool {
+        self.context_state_account == other.context_state_account
+    }
+
+    /// Check that the context state infos reference pairwise distinct context state accounts.
+    ///
+    /// Returns the index of the first info that conflicts with an earlier info.
+    pub fn validate_distinct(infos: &[ContextStateInfo]) -> Result<(), usize> {
+        for (index, info) in infos.iter().enumerate() {
+            if infos[..index]
+                .iter()
+                .any(|earlier| earlier.conflicts_with(info))
+            {
+                return Err(index);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Errors that can occur when validating the pubkeys associated with a context state account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContextStateError {
+    /// The context state authority is the default pubkey
+    DefaultAuthority,
+    /// The context state account is the default pubkey
+    DefaultAccount,
+    /// The context state account is its own authority
+    AccountIsAuthority,
+}
+
+/// Errors that can occur when checking proof data before encoding it in an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProofDataError {
+    /// The proof data consists of only zero bytes
+    AllZeroBytes,
+}
+
+/// Error returned when an instruction does not target the ZkToken Proof program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProgramIdError {
+    /// The program id of the instruction
+    pub found: Pubkey,
+    /// The program id of the ZkToken Proof program
+    pub expected: Pubkey,
+}
+
+/// Errors that can occur when validating the order of proof instructions in a transaction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SequenceError {
+    /// The `CloseContextState` instruction at `index` closes a context state account that is not
+    /// created by an earlier verify instruction
+    CloseWithoutVerify { index: usize },
+    /// The instruction at `index` references a context state account that was already closed
+    UsedAfterClose { index: usize },
+}
+
+/// A problem with an instruction that is reported by `ProofInstruction::dry_run`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DryRunIssue {
+    /// The instruction does not target the ZkToken Proof program
+    ProgramId(ProgramIdError),
+    /// The instruction data is empty or has an unknown discriminant
+    InvalidDiscriminant,
+    /// The length of the proof data does not match the length expected by the instruction
+    InvalidDataLength { expected: usize, found: usize },
+    /// The accounts do not match the accounts expected by the instruction
+    InvalidAccounts,
+}
+
+/// Metadata of a verify instruction in the ZkToken Proof program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VerifyEntry {
+    /// The verify instruction
+    pub instruction: ProofInstruction,
+    /// The instruction discriminant
+    pub discriminant: u8,
+    /// The name of the proof data type for the instruction
+    pub proof_data_name: &'static str,
+    /// The length of the proof data in bytes
+    pub proof_data_len: usize,
+}
+
+impl VerifyEntry {
+    fn new<T: ProofKind>() -> Self {
+        Self {
+            instruction: T::INSTRUCTION,
+            discriminant: ToPrimitive::to_u8(&T::INSTRUCTION).unwrap(),
+            proof_data_name: T::NAME,
+            proof_data_len: T::SIZE,
+        }
+    }
+}
+
+/// Description of an instruction in the ZkToken Proof program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProofInstructionDescription {
+    /// The instruction
+    pub instruction: ProofInstruction,
+    /// The instruction discriminant
+    pub discriminant: u8,
+    /// The name of the proof data type, or `None` for `CloseContextState`
+    pub proof_data_name: Option<&'static str>,
+    /// A short human-readable description of the instruction
+    pub description: &'static str,
+}
+
+/// The role of an instruction in the ZkToken Proof program.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProofCategory {
+    /// The instruction verifies a zero-knowledge proof
+    Verify,
+    /// The instruction closes a proof context state account
+    Close,
+}
+
+/// A proof data type together with the verify instruction that verifies it.
+///
+/// This trait allows generic tooling to be written once over all proof data types.
+pub trait ProofKind: Pod + ZkProofData<<Self as ProofKind>::Context> {
+    /// The context data of the proof
+    type Context: Pod;
+
+    /// The verify instruction for the proof data
+    const INSTRUCTION: ProofInstruction;
+
+    /// The name of the proof data type
+    const NAME: &'static str;
+}
+
+impl ProofKind for ZeroBalanceProofData {
+    type Context = ZeroBalanceProofContext;
+    const INSTRUCTION: ProofInstruction = ProofInstruction::VerifyZeroBalance;
+    const NAME: &'static str = "ZeroBalanceProofData";
+}
+
+impl ProofKind for WithdrawData {
+    type Context = WithdrawProofContext;
+    const INSTRUCTION: ProofInstruction = ProofInstruction::VerifyWithdraw;
+    const NAME: &'static str = "WithdrawData";
+}
+
+impl ProofKind for CiphertextCiphertextEqualityProofData {
+    type Context = CiphertextCiphertextEqualityProofContext;
+    const INSTRUCTION: ProofInstruction = ProofInstruction::VerifyCiphertextCiphertextEquality;
+    const NAME: &'static str = "CiphertextCiphertextEqualityProofData";
+}
+
+impl ProofKind for TransferData {
+    type Context = TransferProofContext;
+    const INSTRUCTION: ProofInstruction = ProofInstruction::VerifyTransfer;
+    const NAME: &'static str = "TransferData";
+}
+
+impl ProofKind for TransferWithFeeData {
+    type Context = TransferWithFeeProofContext;
+    const INSTRUCTION: ProofInstruction = ProofInstruction::VerifyTransferWithFee;
+    const NAME: &'static str = "TransferWithFeeData";
+}
+
+impl ProofKind for PubkeyValidityData {
+    type Context = PubkeyValidityProofContext;
+    const INSTRUCTION: ProofInstruction = ProofInstruction::VerifyPubkeyValidity;
+    const NAME: &'static str = "PubkeyValidityData";
+}
 
 /// Create a `CloseContextState` instruction.
 pub fn close_context_state(
@@ -161,6 +396,71 @@ pub fn close_context_state(
     }
 }
 
+/// Create a `CloseContextState` instruction for each context state account, sending the lamports
+/// of all accounts to the same destination.
+pub fn close_context_states(
+    context_state_infos: &[ContextStateInfo],
+    destination_account: &Pubkey,
+) -> Vec<Instruction> {
+    context_state_infos
+        .iter()
+        .map(|context_state_info| close_context_state(*context_state_info, destination_account))
+        .collect()
+}
+
+/// Create a `CloseContextState` instruction for each context state account with a shared
+/// context state authority and lamport destination.
+///
+/// Returns the index of the first context state account that is a duplicate of an earlier
+/// account.
+pub fn close_context_states_same_authority(
+    context_state_accounts: &[&Pubkey],
+    context_state_authority: &Pubkey,
+    destination_account: &Pubkey,
+) -> Result<Vec<Instruction>, usize> {
+    let context_state_infos: Vec<_> = context_state_accounts
+        .iter()
+        .map(|context_state_account| ContextStateInfo {
+            context_state_account,
+            context_state_authority,
+        })
+        .collect();
+    ContextStateInfo::validate_distinct(&context_state_infos)?;
+
+    Ok(close_context_states(
+        &context_state_infos,
+        destination_account,
+    ))
+}
+
+/// Create a `CloseContextState` instruction, rejecting a default context state authority.
+///
+/// The default pubkey cannot sign for the context state account and therefore, a context state
+/// account with the default pubkey as its authority can never be closed.
+pub fn close_context_state_checked(
+    context_state_info: ContextStateInfo,
+    destination_account: &Pubkey,
+) -> Result<Instruction, ContextStateError> {
+    if context_state_info.context_state_authority == &Pubkey::default() {
+        return Err(ContextStateError::DefaultAuthority);
+    }
+    Ok(close_context_state(context_state_info, destination_account))
+}
+
+/// The seed prefix of a context state authority derived with `derive_context_authority`.
+pub const CONTEXT_STATE_AUTHORITY_SEED: &[u8] = b"context_state_authority";
+
+/// Derive a context state authority as a program derived address tied to a `base` pubkey.
+///
+/// The address is derived from the seeds `[CONTEXT_STATE_AUTHORITY_SEED, base, label]` and
+/// `program_id`. Returns the authority together with its bump seed.
+pub fn derive_context_authority(base: &Pubkey, program_id: &Pubkey, label: &[u8]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CONTEXT_STATE_AUTHORITY_SEED, base.as_ref(), label],
+        program_id,
+    )
+}
+
 /// Create a `VerifyZeroBalance` instruction.
 pub fn verify_zero_balance(
     context_state_info: Option<ContextStateInfo>,
@@ -210,12 +510,118 @@ pub fn verify_pubkey_validity(
     ProofInstruction::VerifyPubkeyValidity.encode_verify_proof(context_state_info, proof_data)
 }
 
+/// Create a `VerifyZeroBalance` instruction preceded by a compute unit limit instruction.
+#[cfg(not(target_os = "solana"))]
+pub fn verify_zero_balance_with_budget(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &ZeroBalanceProofData,
+) -> Vec<Instruction> {
+    ProofInstruction::VerifyZeroBalance
+        .encode_verify_proof_with_budget(context_state_info, proof_data)
+}
+
+/// Create a `VerifyWithdraw` instruction preceded by a compute unit limit instruction.
+#[cfg(not(target_os = "solana"))]
+pub fn verify_withdraw_with_budget(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &WithdrawData,
+) -> Vec<Instruction> {
+    ProofInstruction::VerifyWithdraw.encode_verify_proof_with_budget(context_state_info, proof_data)
+}
+
+/// Create a `VerifyCiphertextCiphertextEquality` instruction preceded by a compute unit limit
+/// instruction.
+#[cfg(not(target_os = "solana"))]
+pub fn verify_ciphertext_ciphertext_equality_with_budget(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &CiphertextCiphertextEqualityProofData,
+) -> Vec<Instruction> {
+    ProofInstruction::VerifyCiphertextCiphertextEquality
+        .encode_verify_proof_with_budget(context_state_info, proof_data)
+}
+
+/// Create a `VerifyTransfer` instruction preceded by a compute unit limit instruction.
+#[cfg(not(target_os = "solana"))]
+pub fn verify_transfer_with_budget(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &TransferData,
+) -> Vec<Instruction> {
+    ProofInstruction::VerifyTransfer.encode_verify_proof_with_budget(context_state_info, proof_data)
+}
+
+/// Create a `VerifyTransferWithFee` instruction preceded by a compute unit limit instruction.
+#[cfg(not(target_os = "solana"))]
+pub fn verify_transfer_with_fee_with_budget(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &TransferWithFeeData,
+) -> Vec<Instruction> {
+    ProofInstruction::VerifyTransferWithFee
+        .encode_verify_proof_with_budget(context_state_info, proof_data)
+}
+
+/// Create a `VerifyPubkeyValidity` instruction preceded by a compute unit limit instruction.
+#[cfg(not(target_os = "solana"))]
+pub fn verify_pubkey_validity_with_budget(
+    context_state_info: Option<ContextStateInfo>,
+    proof_data: &PubkeyValidityData,
+) -> Vec<Instruction> {
+    ProofInstruction::VerifyPubkeyValidity
+        .encode_verify_proof_with_budget(context_state_info, proof_data)
+}
+
 impl ProofInstruction {
+    /// The index of the proof context account in a `CloseContextState` instruction
+    pub const CLOSE_CONTEXT_ACCOUNT_INDEX: usize = 0;
+    /// The index of the lamport destination account in a `CloseContextState` instruction
+    pub const CLOSE_DESTINATION_ACCOUNT_INDEX: usize = 1;
+    /// The index of the context state authority in a `CloseContextState` instruction
+    pub const CLOSE_AUTHORITY_ACCOUNT_INDEX: usize = 2;
+
     pub fn encode_verify_proof<T, U>(
         &self,
         context_state_info: Option<ContextStateInfo>,
         proof_data: &T,
     ) -> Instruction
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        self.encode_verify_proof_for_program(
+            &crate::zk_token_proof_program::id(),
+            context_state_info,
+            proof_data,
+        )
+    }
+
+    /// Encode a verify instruction, rejecting proof data that consists of only zero bytes.
+    ///
+    /// All-zero proof data is structurally valid, but it is usually the result of an
+    /// uninitialized buffer and never verifies.
+    pub fn encode_verify_proof_checked<T, U>(
+        &self,
+        context_state_info: Option<ContextStateInfo>,
+        proof_data: &T,
+    ) -> Result<Instruction, ProofDataError>
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        if bytes_of(proof_data).iter().all(|byte| *byte == 0) {
+            return Err(ProofDataError::AllZeroBytes);
+        }
+        Ok(self.encode_verify_proof(context_state_info, proof_data))
+    }
+
+    /// Encode a verify instruction that targets a ZkToken Proof program deployed at `program_id`.
+    ///
+    /// This is useful for testing against a ZkToken Proof program that is deployed at a
+    /// non-canonical address.
+    pub fn encode_verify_proof_for_program<T, U>(
+        &self,
+        program_id: &Pubkey,
+        context_state_info: Option<ContextStateInfo>,
+        proof_data: &T,
+    ) -> Instruction
     where
         T: Pod + ZkProofData<U>,
         U: Pod,
@@ -229,29 +635,2132 @@ impl ProofInstruction {
             vec![]
         };
 
-        let mut data = vec![ToPrimitive::to_u8(self).unwrap()];
-        data.extend_from_slice(bytes_of(proof_data));
+        let proof_bytes = bytes_of(proof_data);
+
+        let mut data = Vec::with_capacity(1 + proof_bytes.len());
+        data.push(ToPrimitive::to_u8(self).unwrap());
+        data.extend_from_slice(proof_bytes);
 
         Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        }
+    }
+
+    /// Encode a verify instruction with `discriminant` as the leading instruction data byte.
+    ///
+    /// This is a test helper for programs that are deployed with a remapped discriminant space.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn encode_verify_proof_with_discriminant<T, U>(
+        &self,
+        discriminant: u8,
+        context_state_info: Option<ContextStateInfo>,
+        proof_data: &T,
+    ) -> Instruction
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let mut instruction = self.encode_verify_proof(context_state_info, proof_data);
+        instruction.data[0] = discriminant;
+        instruction
+    }
+
+    /// Encode a verify instruction with `extra_accounts` appended after the accounts that are
+    /// expected by the instruction.
+    ///
+    /// The ZkToken Proof program ignores accounts past the proof context accounts. This allows
+    /// programs that wrap the instruction to pass through accounts for their own use. Note that
+    /// the program creates a proof context state whenever accounts are provided, so without
+    /// `context_state_info`, the first two extra accounts are interpreted as the proof context
+    /// accounts.
+    pub fn encode_verify_proof_with_extra_accounts<T, U>(
+        &self,
+        context_state_info: Option<ContextStateInfo>,
+        proof_data: &T,
+        extra_accounts: &[AccountMeta],
+    ) -> Instruction
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let mut instruction = self.encode_verify_proof(context_state_info, proof_data);
+        instruction.accounts.extend_from_slice(extra_accounts);
+        instruction
+    }
+
+    /// Encode a verify instruction by reading exactly `len` bytes of proof data from `reader`.
+    ///
+    /// The proof data is read directly into the instruction data so that large proofs that are
+    /// stored in a file do not need to be buffered separately. An error of kind `InvalidInput` is
+    /// returned if the instruction is not a verify instruction or if `len` does not match the
+    /// proof data length that is expected by the instruction.
+    #[cfg(not(target_os = "solana"))]
+    pub fn encode_verify_proof_from_reader<R: Read>(
+        &self,
+        context_state_info: Option<ContextStateInfo>,
+        reader: &mut R,
+        len: usize,
+    ) -> io::Result<Instruction> {
+        if *self == Self::CloseContextState || len != self.proof_data_len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid proof data length",
+            ));
+        }
+
+        let accounts = if let Some(context_state_info) = context_state_info {
+            vec![
+                AccountMeta::new(*context_state_info.context_state_account, false),
+                AccountMeta::new_readonly(*context_state_info.context_state_authority, false),
+            ]
+        } else {
+            vec![]
+        };
+
+        let mut data = vec![0; 1 + len];
+        data[0] = ToPrimitive::to_u8(self).unwrap();
+        reader.read_exact(&mut data[1..])?;
+
+        Ok(Instruction {
             program_id: crate::zk_token_proof_program::id(),
             accounts,
             data,
+        })
+    }
+
+    /// Encode a verify instruction together with the pubkeys that must sign a transaction
+    /// containing it.
+    ///
+    /// The proof context account owner does not need to sign a verify instruction. The fee
+    /// `payer` always signs. If a proof context state account is created, then the context state
+    /// account must also sign the system instruction that allocates it.
+    pub fn verify_with_signers<T, U>(
+        &self,
+        context_state_info: Option<ContextStateInfo>,
+        proof_data: &T,
+        payer: &Pubkey,
+    ) -> (Instruction, Vec<Pubkey>)
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let mut signers = vec![*payer];
+        if let Some(context_state_info) = context_state_info {
+            signers.push(*context_state_info.context_state_account);
         }
+
+        (
+            self.encode_verify_proof(context_state_info, proof_data),
+            signers,
+        )
     }
 
-    pub fn instruction_type(input: &[u8]) -> Option<Self> {
-        input
-            .first()
-            .and_then(|instruction| FromPrimitive::from_u8(*instruction))
+    /// Encode a verify instruction that creates a proof context state account at the program
+    /// derived address of `seeds` and `program_id`.
+    ///
+    /// The instruction targets the ZkToken Proof program deployed at `proof_program_id`. Returns
+    /// the instruction together with the bump seed of the context state account, which is needed
+    /// to sign for the account with `invoke_signed`.
+    pub fn verify_to_pda<T, U>(
+        &self,
+        proof_data: &T,
+        seeds: &[&[u8]],
+        program_id: &Pubkey,
+        proof_program_id: &Pubkey,
+        context_state_authority: &Pubkey,
+    ) -> (Instruction, u8)
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let (context_state_account, bump_seed) = Pubkey::find_program_address(seeds, program_id);
+        let context_state_info = ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority,
+        };
+
+        (
+            self.encode_verify_proof_for_program(
+                proof_program_id,
+                Some(context_state_info),
+                proof_data,
+            ),
+            bump_seed,
+        )
     }
 
-    pub fn proof_data<T, U>(input: &[u8]) -> Option<&T>
+    /// Append the instruction data for the instruction with `proof_bytes` as its proof data to
+    /// `buf`.
+    ///
+    /// Returns the number of bytes written, which is the length of `proof_bytes` plus one byte
+    /// for the instruction discriminant.
+    pub fn write_into(&self, buf: &mut Vec<u8>, proof_bytes: &[u8]) -> usize {
+        buf.reserve(1 + proof_bytes.len());
+        buf.push(ToPrimitive::to_u8(self).unwrap());
+        buf.extend_from_slice(proof_bytes);
+        1 + proof_bytes.len()
+    }
+
+    /// Encode the instruction discriminant and `proof_bytes` compressed with zstd.
+    ///
+    /// The high bit of the discriminant byte is set to mark the proof data as compressed. The
+    /// output is meant for off-chain transport only: the ZkToken Proof program expects
+    /// uncompressed proof data, so the output must be decoded with `decode_compressed` before it
+    /// is used as instruction data.
+    #[cfg(all(feature = "zstd", not(target_os = "solana")))]
+    pub fn encode_compressed(&self, proof_bytes: &[u8]) -> Vec<u8> {
+        let mut data = vec![ToPrimitive::to_u8(self).unwrap() | COMPRESSED_DISCRIMINANT_FLAG];
+        // Compressing an in-memory buffer into a `Vec` does not fail.
+        data.extend(zstd::stream::encode_all(proof_bytes, 0).expect("zstd compression"));
+        data
+    }
+
+    /// Decode the output of `encode_compressed` into the instruction and the decompressed proof
+    /// bytes.
+    ///
+    /// Returns `None` if the input is not marked as compressed, the discriminant is invalid, or
+    /// the proof data fails to decompress.
+    #[cfg(all(feature = "zstd", not(target_os = "solana")))]
+    pub fn decode_compressed(input: &[u8]) -> Option<(Self, Vec<u8>)> {
+        let (&discriminant, compressed) = input.split_first()?;
+        if discriminant & COMPRESSED_DISCRIMINANT_FLAG == 0 {
+            return None;
+        }
+        let instruction = FromPrimitive::from_u8(discriminant & !COMPRESSED_DISCRIMINANT_FLAG)?;
+        let proof_bytes = zstd::stream::decode_all(compressed).ok()?;
+        Some((instruction, proof_bytes))
+    }
+
+    /// Encode the instruction and `proof_bytes` as a self-describing proof packet.
+    ///
+    /// A proof packet consists of the packet version, the instruction discriminant, the length
+    /// of the proof bytes as a little-endian `u32`, and the proof bytes. The packet format is
+    /// meant for off-chain storage and is distinct from the instruction data format.
+    pub fn to_packet(&self, proof_bytes: &[u8]) -> Vec<u8> {
+        let mut packet = Vec::with_capacity(6 + proof_bytes.len());
+        packet.push(PROOF_PACKET_VERSION);
+        packet.push(ToPrimitive::to_u8(self).unwrap());
+        packet.extend_from_slice(&(proof_bytes.len() as u32).to_le_bytes());
+        packet.extend_from_slice(proof_bytes);
+        packet
+    }
+
+    /// Decode a proof packet that is encoded with `to_packet`.
+    ///
+    /// Returns the packet version, the instruction, and the proof bytes. Returns `None` if the
+    /// packet version is unknown, the discriminant is invalid, or the length of the proof bytes
+    /// does not match the encoded length.
+    pub fn from_packet(bytes: &[u8]) -> Option<(u8, Self, Vec<u8>)> {
+        let (&version, rest) = bytes.split_first()?;
+        if version != PROOF_PACKET_VERSION {
+            return None;
+        }
+        let (&discriminant, rest) = rest.split_first()?;
+        let instruction = FromPrimitive::from_u8(discriminant)?;
+        let len_bytes = rest.get(..4)?;
+        let proof_bytes = &rest[4..];
+        let len = u32::from_le_bytes(len_bytes.try_into().ok()?) as usize;
+        if proof_bytes.len() != len {
+            return None;
+        }
+        Some((version, instruction, proof_bytes.to_vec()))
+    }
+
+    /// Encode a verify instruction preceded by a `SetComputeUnitLimit` instruction that is
+    /// sufficient for the proof verification.
+    ///
+    /// The compute unit limit applies to the whole transaction. If the transaction contains other
+    /// instructions, the caller must account for their compute units separately.
+    #[cfg(not(target_os = "solana"))]
+    pub fn encode_verify_proof_with_budget<T, U>(
+        &self,
+        context_state_info: Option<ContextStateInfo>,
+        proof_data: &T,
+    ) -> Vec<Instruction>
     where
         T: Pod + ZkProofData<U>,
         U: Pod,
     {
-        input
-            .get(1..)
-            .and_then(|data| bytemuck::try_from_bytes(data).ok())
+        let verify_instruction = self.encode_verify_proof(context_state_info, proof_data);
+
+        if let Some(compute_units) = self.recommended_compute_units() {
+            vec![
+                ComputeBudgetInstruction::set_compute_unit_limit(compute_units),
+                verify_instruction,
+            ]
+        } else {
+            vec![verify_instruction]
+        }
+    }
+
+    /// The size of a proof context state account that is created by the instruction.
+    ///
+    /// Returns `None` for `CloseContextState`, which does not create a proof context state.
+    fn context_state_size(&self) -> Option<usize> {
+        match self {
+            Self::CloseContextState => None,
+            Self::VerifyZeroBalance => {
+                Some(size_of::<ProofContextState<ZeroBalanceProofContext>>())
+            }
+            Self::VerifyWithdraw => Some(size_of::<ProofContextState<WithdrawProofContext>>()),
+            Self::VerifyCiphertextCiphertextEquality => Some(size_of::<
+                ProofContextState<CiphertextCiphertextEqualityProofContext>,
+            >()),
+            Self::VerifyTransfer => Some(size_of::<ProofContextState<TransferProofContext>>()),
+            Self::VerifyTransferWithFee => {
+                Some(size_of::<ProofContextState<TransferWithFeeProofContext>>())
+            }
+            Self::VerifyPubkeyValidity => {
+                Some(size_of::<ProofContextState<PubkeyValidityProofContext>>())
+            }
+        }
+    }
+
+    /// The byte offset of the proof context within the data of a proof context state account
+    /// that is created by the instruction.
+    ///
+    /// The proof context follows the `ProofContextStateMeta` header. Returns `None` for
+    /// `CloseContextState`, which does not create a proof context state.
+    pub fn context_data_offset(&self) -> Option<usize> {
+        match self {
+            Self::CloseContextState => None,
+            _ => Some(size_of::<ProofContextStateMeta>()),
+        }
+    }
+
+    /// The lamports that are reclaimed by closing proof context state accounts.
+    ///
+    /// Each context state account is paired with the verify instruction that created it, which
+    /// determines the size of the account. The accounts are assumed to hold exactly the
+    /// rent-exempt minimum balance for their size.
+    pub fn reclaimable_rent(
+        context_states: &[(ContextStateInfo, ProofInstruction)],
+        rent: &Rent,
+    ) -> u64 {
+        context_states
+            .iter()
+            .filter_map(|(_, instruction)| instruction.context_state_size())
+            .map(|size| rent.minimum_balance(size))
+            .sum()
+    }
+
+    /// The `TypeId` of the proof data that is expected by the instruction.
+    ///
+    /// Returns `None` for `CloseContextState`, which does not carry proof data.
+    pub fn proof_type_id(&self) -> Option<TypeId> {
+        match self {
+            Self::CloseContextState => None,
+            Self::VerifyZeroBalance => Some(TypeId::of::<ZeroBalanceProofData>()),
+            Self::VerifyWithdraw => Some(TypeId::of::<WithdrawData>()),
+            Self::VerifyCiphertextCiphertextEquality => {
+                Some(TypeId::of::<CiphertextCiphertextEqualityProofData>())
+            }
+            Self::VerifyTransfer => Some(TypeId::of::<TransferData>()),
+            Self::VerifyTransferWithFee => Some(TypeId::of::<TransferWithFeeData>()),
+            Self::VerifyPubkeyValidity => Some(TypeId::of::<PubkeyValidityData>()),
+        }
+    }
+
+    /// The role of the instruction.
+    pub fn category(&self) -> ProofCategory {
+        match self {
+            Self::CloseContextState => ProofCategory::Close,
+            Self::VerifyZeroBalance
+            | Self::VerifyWithdraw
+            | Self::VerifyCiphertextCiphertextEquality
+            | Self::VerifyTransfer
+            | Self::VerifyTransferWithFee
+            | Self::VerifyPubkeyValidity => ProofCategory::Verify,
+        }
+    }
+
+    /// Returns whether the instruction verifies a zero-knowledge proof.
+    pub fn is_verify(&self) -> bool {
+        self.category() == ProofCategory::Verify
+    }
+
+    /// Returns whether the instruction closes a proof context state account.
+    pub fn is_close(&self) -> bool {
+        self.category() == ProofCategory::Close
+    }
+
+    /// The length of the proof data that is expected by the instruction.
+    pub fn proof_data_len(&self) -> usize {
+        match self {
+            Self::CloseContextState => 0,
+            Self::VerifyZeroBalance => ZeroBalanceProofData::SIZE,
+            Self::VerifyWithdraw => WithdrawData::SIZE,
+            Self::VerifyCiphertextCiphertextEquality => CiphertextCiphertextEqualityProofData::SIZE,
+            Self::VerifyTransfer => TransferData::SIZE,
+            Self::VerifyTransferWithFee => TransferWithFeeData::SIZE,
+            Self::VerifyPubkeyValidity => PubkeyValidityData::SIZE,
+        }
+    }
+
+    /// Returns whether the instruction with its proof data included in the instruction data fits
+    /// in a transaction.
+    ///
+    /// Proofs that do not fit must be verified with the proof data split across multiple
+    /// transactions or stored in an account.
+    pub fn fits_in_instruction(&self) -> bool {
+        self.within_budget(MAX_INLINE_INSTRUCTION_DATA_LEN)
+    }
+
+    /// The length of the instruction data with the proof data included in the instruction data,
+    /// which is the discriminant followed by the proof data.
+    pub fn encoded_size(&self) -> usize {
+        1 + self.proof_data_len()
+    }
+
+    /// Returns whether the instruction data with the proof data included in the instruction data
+    /// is at most `budget_bytes` long.
+    pub fn within_budget(&self, budget_bytes: usize) -> bool {
+        self.encoded_size() <= budget_bytes
+    }
+
+    /// The compute unit limit for a transaction consisting of a `SetComputeUnitLimit` instruction
+    /// and the instruction.
+    ///
+    /// The limit is the number of compute units that the ZkToken Proof program consumes to
+    /// process the instruction, plus `SET_COMPUTE_UNIT_LIMIT_COMPUTE_UNITS` for the
+    /// `SetComputeUnitLimit` instruction itself and a safety margin of
+    /// `COMPUTE_UNIT_LIMIT_MARGIN`.
+    ///
+    /// `CloseContextState` does not perform any proof verification and fits well within the
+    /// default compute unit limit, so no estimate is returned for it.
+    pub fn recommended_compute_units(&self) -> Option<u32> {
+        let verify_compute_units = match self {
+            Self::CloseContextState => return None,
+            Self::VerifyZeroBalance => VERIFY_ZERO_BALANCE_COMPUTE_UNITS,
+            Self::VerifyWithdraw => VERIFY_WITHDRAW_COMPUTE_UNITS,
+            Self::VerifyCiphertextCiphertextEquality => {
+                VERIFY_CIPHERTEXT_CIPHERTEXT_EQUALITY_COMPUTE_UNITS
+            }
+            Self::VerifyTransfer => VERIFY_TRANSFER_COMPUTE_UNITS,
+            Self::VerifyTransferWithFee => VERIFY_TRANSFER_WITH_FEE_COMPUTE_UNITS,
+            Self::VerifyPubkeyValidity => VERIFY_PUBKEY_VALIDITY_COMPUTE_UNITS,
+        };
+        // The verification costs are far below `u32::MAX`
+        Some(
+            verify_compute_units as u32
+                + SET_COMPUTE_UNIT_LIMIT_COMPUTE_UNITS
+                + COMPUTE_UNIT_LIMIT_MARGIN,
+        )
+    }
+
+    /// Returns whether encoding `proof_data` into an instruction and decoding it back yields the
+    /// original proof data bytes.
+    ///
+    /// This is a test helper that downstream proof data types can use to check the canonicity of
+    /// their instruction encoding.
+    #[cfg(any(test, feature = "test-utils"))]
+    pub fn canonical_roundtrip<T, U>(&self, proof_data: &T) -> bool
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        let instruction = self.encode_verify_proof(None, proof_data);
+
+        Self::instruction_type(&instruction.data) == Some(*self)
+            && Self::proof_data::<T, U>(&instruction.data)
+                .map(|decoded| bytes_of(decoded) == bytes_of(proof_data))
+                .unwrap_or(false)
+    }
+
+    /// Check that an instruction targets the ZkToken Proof program.
+    ///
+    /// This should be used to validate a proof instruction before forwarding it via a
+    /// cross-program invocation.
+    pub fn ensure_program_id(instruction: &Instruction) -> Result<(), ProgramIdError> {
+        let expected = crate::zk_token_proof_program::id();
+        if instruction.program_id == expected {
+            Ok(())
+        } else {
+            Err(ProgramIdError {
+                found: instruction.program_id,
+                expected,
+            })
+        }
+    }
+
+    /// Return a copy of an instruction with the proof data replaced by zero bytes.
+    ///
+    /// The program id, the discriminant byte, the accounts, and the length of the instruction
+    /// data are preserved, so the redacted instruction can be logged without the proof bytes.
+    pub fn redact_for_log(instruction: &Instruction) -> Instruction {
+        let mut data = vec![0; instruction.data.len()];
+        if let (Some(redacted_discriminant), Some(discriminant)) =
+            (data.first_mut(), instruction.data.first())
+        {
+            *redacted_discriminant = *discriminant;
+        }
+
+        Instruction {
+            program_id: instruction.program_id,
+            accounts: instruction.accounts.clone(),
+            data,
+        }
+    }
+
+    /// Check that an instruction is a well-formed proof instruction, reporting every problem.
+    ///
+    /// The program id, the discriminant, the proof data length, and the account layout are
+    /// checked. The proof itself is not verified. If the discriminant is invalid, the data length
+    /// and accounts cannot be checked and are not reported.
+    pub fn dry_run(instruction: &Instruction) -> Result<(), Vec<DryRunIssue>> {
+        let mut issues = vec![];
+
+        if let Err(err) = Self::ensure_program_id(instruction) {
+            issues.push(DryRunIssue::ProgramId(err));
+        }
+
+        match Self::instruction_type(&instruction.data) {
+            None => issues.push(DryRunIssue::InvalidDiscriminant),
+            Some(proof_instruction) => {
+                let expected = proof_instruction.proof_data_len();
+                let found = instruction.data.len() - 1;
+                if found != expected {
+                    issues.push(DryRunIssue::InvalidDataLength { expected, found });
+                }
+
+                let accounts = &instruction.accounts;
+                let valid_accounts = if proof_instruction == Self::CloseContextState {
+                    accounts.len() >= 3
+                        && accounts[Self::CLOSE_CONTEXT_ACCOUNT_INDEX].is_writable
+                        && accounts[Self::CLOSE_DESTINATION_ACCOUNT_INDEX].is_writable
+                        && accounts[Self::CLOSE_AUTHORITY_ACCOUNT_INDEX].is_signer
+                        && accounts[Self::CLOSE_CONTEXT_ACCOUNT_INDEX].pubkey
+                            != accounts[Self::CLOSE_DESTINATION_ACCOUNT_INDEX].pubkey
+                } else {
+                    accounts.is_empty() || (accounts.len() >= 2 && accounts[0].is_writable)
+                };
+                if !valid_accounts {
+                    issues.push(DryRunIssue::InvalidAccounts);
+                }
+            }
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(issues)
+        }
+    }
+
+    /// Return the pubkeys of the accounts that are flagged as signers in the account list of an
+    /// instruction.
+    ///
+    /// The verify instructions do not declare any signers. The `CloseContextState` instruction
+    /// declares the context state authority as a signer.
+    pub fn signer_accounts(accounts: &[AccountMeta]) -> Vec<Pubkey> {
+        accounts
+            .iter()
+            .filter(|account| account.is_signer)
+            .map(|account| account.pubkey)
+            .collect()
+    }
+
+    /// Compile a list of instructions into a `v0` transaction message.
+    ///
+    /// Accounts that are not signers or invoked programs are loaded from `lookup_tables` where
+    /// possible. Note that program ids, including the ZkToken Proof program id, are always
+    /// included in the static account keys of a message and cannot be loaded from lookup tables.
+    #[cfg(not(target_os = "solana"))]
+    pub fn into_message(
+        instructions: Vec<Instruction>,
+        payer: &Pubkey,
+        lookup_tables: &[AddressLookupTableAccount],
+        recent_blockhash: Hash,
+    ) -> Result<v0::Message, CompileError> {
+        v0::Message::try_compile(payer, &instructions, lookup_tables, recent_blockhash)
+    }
+
+    /// Check that every instruction that targets the ZkToken Proof program has a valid
+    /// discriminant.
+    ///
+    /// Instructions for other programs are ignored. Returns the indices of the instructions
+    /// with a missing or unknown discriminant.
+    pub fn validate_all(instructions: &[Instruction]) -> Result<(), Vec<usize>> {
+        let invalid_indices: Vec<usize> = instructions
+            .iter()
+            .enumerate()
+            .filter(|(_, instruction)| {
+                instruction.program_id == crate::zk_token_proof_program::id()
+                    && Self::instruction_type(&instruction.data).is_none()
+            })
+            .map(|(index, _)| index)
+            .collect();
+
+        if invalid_indices.is_empty() {
+            Ok(())
+        } else {
+            Err(invalid_indices)
+        }
+    }
+
+    /// Validate the order of proof instructions in a list of instructions.
+    ///
+    /// Every `CloseContextState` instruction must close a context state account that is created
+    /// by an earlier verify instruction in the list, and no instruction may reference a context
+    /// state account after it is closed.
+    pub fn validate_instruction_sequence(
+        instructions: &[Instruction],
+    ) -> Result<(), SequenceError> {
+        let mut created_accounts: Vec<Pubkey> = vec![];
+        let mut closed_accounts: Vec<Pubkey> = vec![];
+
+        for (index, instruction) in instructions.iter().enumerate() {
+            if instruction.program_id != crate::zk_token_proof_program::id() {
+                if instruction
+                    .accounts
+                    .iter()
+                    .any(|account| closed_accounts.contains(&account.pubkey))
+                {
+                    return Err(SequenceError::UsedAfterClose { index });
+                }
+                continue;
+            }
+
+            match Self::instruction_type(&instruction.data) {
+                Some(Self::CloseContextState) => {
+                    let context_state_account = instruction
+                        .accounts
+                        .first()
+                        .map(|account| account.pubkey)
+                        .ok_or(SequenceError::CloseWithoutVerify { index })?;
+
+                    let position = created_accounts
+                        .iter()
+                        .position(|account| *account == context_state_account)
+                        .ok_or(SequenceError::CloseWithoutVerify { index })?;
+                    created_accounts.swap_remove(position);
+                    closed_accounts.push(context_state_account);
+                }
+                Some(_) if Self::creates_context(&instruction.data, &instruction.accounts) => {
+                    let context_state_account = instruction.accounts[0].pubkey;
+                    if closed_accounts.contains(&context_state_account) {
+                        return Err(SequenceError::UsedAfterClose { index });
+                    }
+                    created_accounts.push(context_state_account);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Count the unique accounts that are referenced by a list of instructions.
+    ///
+    /// The count includes the program ids of the instructions, which are also account keys of a
+    /// transaction, but not the fee payer unless it is referenced by an instruction.
+    pub fn count_unique_accounts(instructions: &[Instruction]) -> usize {
+        let mut accounts: Vec<&Pubkey> = instructions
+            .iter()
+            .flat_map(|instruction| {
+                std::iter::once(&instruction.program_id)
+                    .chain(instruction.accounts.iter().map(|account| &account.pubkey))
+            })
+            .collect();
+        accounts.sort_unstable();
+        accounts.dedup();
+        accounts.len()
+    }
+
+    /// A short human-readable description of the instruction.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::CloseContextState => "Close a proof context state account",
+            Self::VerifyZeroBalance => "Verify that a ciphertext encrypts zero",
+            Self::VerifyWithdraw => {
+                "Verify that an account holds sufficient balance for a withdrawal"
+            }
+            Self::VerifyCiphertextCiphertextEquality => {
+                "Verify that two ciphertexts encrypt the same amount"
+            }
+            Self::VerifyTransfer => "Verify the ciphertexts and balance of a transfer",
+            Self::VerifyTransferWithFee => {
+                "Verify the ciphertexts, balance, and fee of a transfer with fee"
+            }
+            Self::VerifyPubkeyValidity => "Verify that an ElGamal public key is well-formed",
+        }
+    }
+
+    /// Describe all instructions in the ZkToken Proof program in the order of their
+    /// discriminants.
+    pub fn describe_all() -> Vec<ProofInstructionDescription> {
+        (0..=u8::MAX)
+            .map_while(FromPrimitive::from_u8)
+            .map(|instruction: Self| ProofInstructionDescription {
+                instruction,
+                discriminant: ToPrimitive::to_u8(&instruction).unwrap(),
+                proof_data_name: Self::verify_catalog()
+                    .into_iter()
+                    .find(|entry| entry.instruction == instruction)
+                    .map(|entry| entry.proof_data_name),
+                description: instruction.description(),
+            })
+            .collect()
+    }
+
+    /// A catalog of all verify instructions in the ZkToken Proof program.
+    pub fn verify_catalog() -> Vec<VerifyEntry> {
+        vec![
+            VerifyEntry::new::<ZeroBalanceProofData>(),
+            VerifyEntry::new::<WithdrawData>(),
+            VerifyEntry::new::<CiphertextCiphertextEqualityProofData>(),
+            VerifyEntry::new::<TransferData>(),
+            VerifyEntry::new::<TransferWithFeeData>(),
+            VerifyEntry::new::<PubkeyValidityData>(),
+        ]
+    }
+
+    pub fn instruction_type(input: &[u8]) -> Option<Self> {
+        input
+            .first()
+            .and_then(|instruction| FromPrimitive::from_u8(*instruction))
+    }
+
+    /// Returns whether the instruction is a verify instruction that creates a proof context state
+    /// account.
+    ///
+    /// A verify instruction creates a proof context state account when it is provided with the
+    /// proof context account and its owner. Otherwise, the proof is verified without storing its
+    /// context.
+    pub fn creates_context(input_data: &[u8], accounts: &[AccountMeta]) -> bool {
+        match Self::instruction_type(input_data) {
+            None | Some(Self::CloseContextState) => false,
+            Some(_) => accounts.len() >= 2,
+        }
+    }
+
+    /// The proof context state account of a verify instruction that creates a proof context
+    /// state account.
+    ///
+    /// Returns `None` if the instruction verifies a proof without storing its context or is not
+    /// a verify instruction. The returned pubkey can be compared against an expected address,
+    /// e.g. one that is derived with `derive_context_authority`.
+    pub fn context_account(instruction: &Instruction) -> Option<&Pubkey> {
+        if Self::creates_context(&instruction.data, &instruction.accounts) {
+            instruction.accounts.first().map(|account| &account.pubkey)
+        } else {
+            None
+        }
+    }
+
+    /// Returns whether two instruction data attest to the same proof.
+    ///
+    /// The two instruction data are considered equal if they have the same valid instruction
+    /// discriminant and byte-equal proof data.
+    pub fn proofs_equal(a: &[u8], b: &[u8]) -> bool {
+        match (Self::instruction_type(a), Self::instruction_type(b)) {
+            (Some(instruction_a), Some(instruction_b)) => {
+                instruction_a == instruction_b && a.get(1..) == b.get(1..)
+            }
+            _ => false,
+        }
+    }
+
+    /// A one-line human-readable summary of a proof instruction for debugging.
+    ///
+    /// The summary contains the instruction name, the context state accounts if present, and the
+    /// length of the proof data for verify instructions.
+    pub fn debug_summary(input: &[u8], accounts: &[AccountMeta]) -> String {
+        let account = |index: usize| {
+            accounts
+                .get(index)
+                .map(|account| account.pubkey.to_string())
+                .unwrap_or_else(|| "<missing>".to_string())
+        };
+
+        match Self::instruction_type(input) {
+            None => match input.first() {
+                Some(discriminant) => format!("Unknown discriminant {discriminant}"),
+                None => "Empty instruction data".to_string(),
+            },
+            Some(Self::CloseContextState) => {
+                format!("CloseContextState ctx={} dst={}", account(0), account(1))
+            }
+            Some(instruction) => {
+                let proof_data_len = input.len().saturating_sub(1);
+                if Self::creates_context(input, accounts) {
+                    format!(
+                        "{instruction:?} ctx={} owner={} {proof_data_len}B",
+                        account(0),
+                        account(1),
+                    )
+                } else {
+                    format!("{instruction:?} inline {proof_data_len}B")
+                }
+            }
+        }
+    }
+
+    /// Decode the instruction type at the start of `input` together with the number of bytes that
+    /// the instruction data occupies.
+    ///
+    /// The instruction data consists of the one-byte discriminant followed by the proof data of
+    /// the instruction. This can be used to walk a buffer of back-to-back instruction data.
+    pub fn decode_with_len(input: &[u8]) -> Option<(Self, usize)> {
+        let instruction = Self::instruction_type(input)?;
+        let len = 1 + instruction.proof_data_len();
+        if input.len() < len {
+            return None;
+        }
+        Some((instruction, len))
+    }
+
+    pub fn proof_data<T, U>(input: &[u8]) -> Option<&T>
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        input
+            .get(1..)
+            .and_then(|data| bytemuck::try_from_bytes(data).ok())
+    }
+
+    /// Check that the context data of the proof in the instruction data `input` is equal to
+    /// `expected`.
+    ///
+    /// The context data is compared byte-wise. Returns `false` if the proof data cannot be
+    /// decoded.
+    pub fn check_context<T, U>(input: &[u8], expected: &U) -> bool
+    where
+        T: Pod + ZkProofData<U>,
+        U: Pod,
+    {
+        Self::proof_data::<T, U>(input)
+            .map(|proof_data| bytes_of(proof_data.context_data()) == bytes_of(expected))
+            .unwrap_or(false)
+    }
+}
+
+/// Proof data fixtures used for testing and benchmarking
+#[cfg(all(any(test, feature = "test-utils"), not(target_os = "solana")))]
+pub mod test_utils {
+    use {
+        super::*,
+        crate::encryption::{elgamal::ElGamalKeypair, pedersen::PedersenOpening},
+    };
+
+    pub fn zero_balance_proof_data() -> ZeroBalanceProofData {
+        let keypair = ElGamalKeypair::new_rand();
+        let ciphertext = keypair.public.encrypt(0_u64);
+        ZeroBalanceProofData::new(&keypair, &ciphertext).unwrap()
+    }
+
+    pub fn withdraw_proof_data() -> WithdrawData {
+        let keypair = ElGamalKeypair::new_rand();
+        let current_balance: u64 = 77;
+        let current_ciphertext = keypair.public.encrypt(current_balance);
+        WithdrawData::new(55, &keypair, current_balance, &current_ciphertext).unwrap()
+    }
+
+    pub fn ciphertext_ciphertext_equality_proof_data() -> CiphertextCiphertextEqualityProofData {
+        let source_keypair = ElGamalKeypair::new_rand();
+        let destination_keypair = ElGamalKeypair::new_rand();
+
+        let amount: u64 = 55;
+        let source_ciphertext = source_keypair.public.encrypt(amount);
+
+        let destination_opening = PedersenOpening::new_rand();
+        let destination_ciphertext = destination_keypair
+            .public
+            .encrypt_with(amount, &destination_opening);
+
+        CiphertextCiphertextEqualityProofData::new(
+            &source_keypair,
+            &destination_keypair.public,
+            &source_ciphertext,
+            &destination_ciphertext,
+            &destination_opening,
+            amount,
+        )
+        .unwrap()
+    }
+
+    pub fn transfer_proof_data() -> TransferData {
+        let source_keypair = ElGamalKeypair::new_rand();
+        let destination_pubkey = ElGamalKeypair::new_rand().public;
+        let auditor_pubkey = ElGamalKeypair::new_rand().public;
+
+        let spendable_balance: u64 = 77;
+        let spendable_ciphertext = source_keypair.public.encrypt(spendable_balance);
+
+        TransferData::new(
+            55,
+            (spendable_balance, &spendable_ciphertext),
+            &source_keypair,
+            (&destination_pubkey, &auditor_pubkey),
+        )
+        .unwrap()
+    }
+
+    pub fn transfer_with_fee_proof_data() -> TransferWithFeeData {
+        let source_keypair = ElGamalKeypair::new_rand();
+        let destination_pubkey = ElGamalKeypair::new_rand().public;
+        let auditor_pubkey = ElGamalKeypair::new_rand().public;
+        let withdraw_withheld_authority_pubkey = ElGamalKeypair::new_rand().public;
+
+        let spendable_balance: u64 = 120;
+        let spendable_ciphertext = source_keypair.public.encrypt(spendable_balance);
+
+        let fee_parameters = FeeParameters {
+            fee_rate_basis_points: 400,
+            maximum_fee: 3,
+        };
+
+        TransferWithFeeData::new(
+            100,
+            (spendable_balance, &spendable_ciphertext),
+            &source_keypair,
+            (&destination_pubkey, &auditor_pubkey),
+            fee_parameters,
+            &withdraw_withheld_authority_pubkey,
+        )
+        .unwrap()
+    }
+
+    pub fn pubkey_validity_proof_data() -> PubkeyValidityData {
+        let keypair = ElGamalKeypair::new_rand();
+        PubkeyValidityData::new(&keypair).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use {
+        super::{test_utils::*, *},
+        bytemuck::Zeroable,
+        solana_sdk::compute_budget,
+    };
+
+    const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+    const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+    #[test]
+    fn test_recommended_compute_units() {
+        // the heavy proofs do not fit within the default per-instruction compute unit limit
+        for instruction in [
+            ProofInstruction::VerifyTransfer,
+            ProofInstruction::VerifyTransferWithFee,
+        ] {
+            let compute_units = instruction.recommended_compute_units().unwrap();
+            assert!(compute_units > DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT);
+            assert!(compute_units <= MAX_COMPUTE_UNIT_LIMIT);
+        }
+
+        assert!(ProofInstruction::CloseContextState
+            .recommended_compute_units()
+            .is_none());
+    }
+
+    #[test]
+    fn test_verify_with_budget() {
+        let proof_data = pubkey_validity_proof_data();
+
+        let instructions = verify_pubkey_validity_with_budget(None, &proof_data);
+        assert_eq!(instructions.len(), 2);
+        assert!(compute_budget::check_id(&instructions[0].program_id));
+        // the limit covers the verification, the compute budget instruction, and the margin
+        let compute_unit_limit = VERIFY_PUBKEY_VALIDITY_COMPUTE_UNITS as u32
+            + SET_COMPUTE_UNIT_LIMIT_COMPUTE_UNITS
+            + COMPUTE_UNIT_LIMIT_MARGIN;
+        assert_eq!(
+            ProofInstruction::VerifyPubkeyValidity.recommended_compute_units(),
+            Some(compute_unit_limit)
+        );
+        assert_eq!(
+            instructions[0],
+            ComputeBudgetInstruction::set_compute_unit_limit(compute_unit_limit)
+        );
+        assert_eq!(instructions[1], verify_pubkey_validity(None, &proof_data));
+    }
+
+    #[test]
+    fn test_creates_context() {
+        let proof_data = pubkey_validity_proof_data();
+
+        let instruction = verify_pubkey_validity(None, &proof_data);
+        assert!(!ProofInstruction::creates_context(
+            &instruction.data,
+            &instruction.accounts
+        ));
+
+        let context_state_info = ContextStateInfo {
+            context_state_account: &Pubkey::new_unique(),
+            context_state_authority: &Pubkey::new_unique(),
+        };
+        let instruction = verify_pubkey_validity(Some(context_state_info), &proof_data);
+        assert!(ProofInstruction::creates_context(
+            &instruction.data,
+            &instruction.accounts
+        ));
+
+        let instruction = close_context_state(context_state_info, &Pubkey::new_unique());
+        assert!(!ProofInstruction::creates_context(
+            &instruction.data,
+            &instruction.accounts
+        ));
+    }
+
+    #[test]
+    fn test_canonical_roundtrip() {
+        let zero_balance_proof_data = zero_balance_proof_data();
+        assert!(ProofInstruction::VerifyZeroBalance.canonical_roundtrip(&zero_balance_proof_data));
+        assert!(ProofInstruction::VerifyWithdraw.canonical_roundtrip(&withdraw_proof_data()));
+        assert!(ProofInstruction::VerifyCiphertextCiphertextEquality
+            .canonical_roundtrip(&ciphertext_ciphertext_equality_proof_data()));
+        assert!(ProofInstruction::VerifyTransfer.canonical_roundtrip(&transfer_proof_data()));
+        assert!(ProofInstruction::VerifyTransferWithFee
+            .canonical_roundtrip(&transfer_with_fee_proof_data()));
+        assert!(ProofInstruction::VerifyPubkeyValidity
+            .canonical_roundtrip(&pubkey_validity_proof_data()));
+    }
+
+    #[test]
+    fn test_close_context_state_checked() {
+        let context_state_account = Pubkey::new_unique();
+        let destination_account = Pubkey::new_unique();
+
+        let context_state_info = ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority: &Pubkey::default(),
+        };
+        assert_eq!(
+            close_context_state_checked(context_state_info, &destination_account),
+            Err(ContextStateError::DefaultAuthority)
+        );
+
+        let context_state_info = ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority: &Pubkey::new_unique(),
+        };
+        assert_eq!(
+            close_context_state_checked(context_state_info, &destination_account),
+            Ok(close_context_state(
+                context_state_info,
+                &destination_account
+            ))
+        );
+    }
+
+    #[test]
+    fn test_ensure_program_id() {
+        let mut instruction = verify_pubkey_validity(None, &pubkey_validity_proof_data());
+        assert_eq!(ProofInstruction::ensure_program_id(&instruction), Ok(()));
+
+        let program_id = Pubkey::new_unique();
+        instruction.program_id = program_id;
+        assert_eq!(
+            ProofInstruction::ensure_program_id(&instruction),
+            Err(ProgramIdError {
+                found: program_id,
+                expected: crate::zk_token_proof_program::id(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_encode_verify_proof_for_program() {
+        let proof_data = pubkey_validity_proof_data();
+        let program_id = Pubkey::new_unique();
+
+        let instruction = ProofInstruction::VerifyPubkeyValidity.encode_verify_proof_for_program(
+            &program_id,
+            None,
+            &proof_data,
+        );
+        assert_eq!(instruction.program_id, program_id);
+        assert_eq!(
+            instruction.data,
+            verify_pubkey_validity(None, &proof_data).data
+        );
+    }
+
+    #[test]
+    fn test_write_into() {
+        let proof_data = pubkey_validity_proof_data();
+        let instruction = verify_pubkey_validity(None, &proof_data);
+
+        let mut buf = vec![0xff];
+        let written =
+            ProofInstruction::VerifyPubkeyValidity.write_into(&mut buf, bytes_of(&proof_data));
+        assert_eq!(written, instruction.data.len());
+        assert_eq!(buf[0], 0xff);
+        assert_eq!(&buf[1..], instruction.data.as_slice());
+    }
+
+    #[test]
+    fn test_verify_with_signers() {
+        let proof_data = pubkey_validity_proof_data();
+        let payer = Pubkey::new_unique();
+
+        let (instruction, signers) =
+            ProofInstruction::VerifyPubkeyValidity.verify_with_signers(None, &proof_data, &payer);
+        assert_eq!(instruction, verify_pubkey_validity(None, &proof_data));
+        assert_eq!(signers, vec![payer]);
+
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let context_state_info = ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority: &context_state_authority,
+        };
+        let (instruction, signers) = ProofInstruction::VerifyPubkeyValidity.verify_with_signers(
+            Some(context_state_info),
+            &proof_data,
+            &payer,
+        );
+        assert_eq!(
+            instruction,
+            verify_pubkey_validity(Some(context_state_info), &proof_data)
+        );
+        assert_eq!(signers, vec![payer, context_state_account]);
+        assert!(!signers.contains(&context_state_authority));
+    }
+
+    #[test]
+    fn test_proofs_equal() {
+        let proof_data = pubkey_validity_proof_data();
+        let instruction = verify_pubkey_validity(None, &proof_data);
+        let instruction_data = instruction.data.clone();
+
+        assert!(ProofInstruction::proofs_equal(
+            &instruction.data,
+            &instruction_data
+        ));
+
+        let mut other_discriminant = instruction_data;
+        other_discriminant[0] = ToPrimitive::to_u8(&ProofInstruction::VerifyZeroBalance).unwrap();
+        assert!(!ProofInstruction::proofs_equal(
+            &instruction.data,
+            &other_discriminant
+        ));
+
+        let other_instruction = verify_pubkey_validity(None, &pubkey_validity_proof_data());
+        assert!(!ProofInstruction::proofs_equal(
+            &instruction.data,
+            &other_instruction.data
+        ));
+    }
+
+    #[test]
+    fn test_verify_catalog() {
+        let catalog = ProofInstruction::verify_catalog();
+
+        let verify_instructions: Vec<ProofInstruction> = (0..=u8::MAX)
+            .filter_map(FromPrimitive::from_u8)
+            .filter(|instruction| *instruction != ProofInstruction::CloseContextState)
+            .collect();
+        assert_eq!(catalog.len(), verify_instructions.len());
+
+        for instruction in verify_instructions {
+            let entries: Vec<&VerifyEntry> = catalog
+                .iter()
+                .filter(|entry| entry.instruction == instruction)
+                .collect();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(
+                entries[0].discriminant,
+                ToPrimitive::to_u8(&instruction).unwrap()
+            );
+        }
+
+        let transfer_entry = catalog
+            .iter()
+            .find(|entry| entry.instruction == ProofInstruction::VerifyTransfer)
+            .unwrap();
+        assert_eq!(transfer_entry.proof_data_name, "TransferData");
+        assert_eq!(transfer_entry.proof_data_len, size_of::<TransferData>());
+    }
+
+    #[test]
+    fn test_verify_to_pda() {
+        let proof_data = pubkey_validity_proof_data();
+        let program_id = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let seeds: &[&[u8]] = &[b"context-state", context_state_authority.as_ref()];
+
+        let (instruction, bump_seed) = ProofInstruction::VerifyPubkeyValidity.verify_to_pda(
+            &proof_data,
+            seeds,
+            &program_id,
+            &crate::zk_token_proof_program::id(),
+            &context_state_authority,
+        );
+
+        let (expected_account, expected_bump_seed) =
+            Pubkey::find_program_address(seeds, &program_id);
+        assert_eq!(bump_seed, expected_bump_seed);
+        assert_eq!(instruction.accounts[0].pubkey, expected_account);
+        assert_eq!(instruction.accounts[1].pubkey, context_state_authority);
+        assert_eq!(instruction.program_id, crate::zk_token_proof_program::id());
+    }
+
+    #[test]
+    fn test_validate_instruction_sequence() {
+        let proof_data = pubkey_validity_proof_data();
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let context_state_info = ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority: &context_state_authority,
+        };
+
+        let verify = verify_pubkey_validity(Some(context_state_info), &proof_data);
+        let consume = Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![AccountMeta::new_readonly(context_state_account, false)],
+            data: vec![],
+        };
+        let close = close_context_state(context_state_info, &Pubkey::new_unique());
+
+        assert_eq!(
+            ProofInstruction::validate_instruction_sequence(&[
+                verify.clone(),
+                consume.clone(),
+                close.clone(),
+            ]),
+            Ok(())
+        );
+
+        assert_eq!(
+            ProofInstruction::validate_instruction_sequence(&[close.clone(), verify.clone()]),
+            Err(SequenceError::CloseWithoutVerify { index: 0 })
+        );
+
+        assert_eq!(
+            ProofInstruction::validate_instruction_sequence(&[verify, close, consume]),
+            Err(SequenceError::UsedAfterClose { index: 2 })
+        );
+    }
+
+    #[test]
+    fn test_debug_summary() {
+        let proof_data = pubkey_validity_proof_data();
+        let instruction = verify_pubkey_validity(None, &proof_data);
+        assert_eq!(
+            ProofInstruction::debug_summary(&instruction.data, &instruction.accounts),
+            format!(
+                "VerifyPubkeyValidity inline {}B",
+                size_of::<PubkeyValidityData>()
+            ),
+        );
+
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let destination_account = Pubkey::new_unique();
+        let context_state_info = ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority: &context_state_authority,
+        };
+
+        let instruction = verify_pubkey_validity(Some(context_state_info), &proof_data);
+        assert_eq!(
+            ProofInstruction::debug_summary(&instruction.data, &instruction.accounts),
+            format!(
+                "VerifyPubkeyValidity ctx={} owner={} {}B",
+                context_state_account,
+                context_state_authority,
+                size_of::<PubkeyValidityData>()
+            ),
+        );
+
+        let instruction = close_context_state(context_state_info, &destination_account);
+        assert_eq!(
+            ProofInstruction::debug_summary(&instruction.data, &instruction.accounts),
+            format!("CloseContextState ctx={context_state_account} dst={destination_account}"),
+        );
+
+        assert_eq!(
+            ProofInstruction::debug_summary(&[255], &[]),
+            "Unknown discriminant 255"
+        );
+    }
+
+    #[test]
+    fn test_fits_in_instruction() {
+        assert!(ProofInstruction::CloseContextState.fits_in_instruction());
+        assert!(ProofInstruction::VerifyZeroBalance.fits_in_instruction());
+        assert!(ProofInstruction::VerifyWithdraw.fits_in_instruction());
+        assert!(ProofInstruction::VerifyCiphertextCiphertextEquality.fits_in_instruction());
+        assert!(ProofInstruction::VerifyPubkeyValidity.fits_in_instruction());
+
+        assert!(!ProofInstruction::VerifyTransfer.fits_in_instruction());
+        assert!(!ProofInstruction::VerifyTransferWithFee.fits_in_instruction());
+
+        for entry in ProofInstruction::verify_catalog() {
+            assert_eq!(entry.instruction.proof_data_len(), entry.proof_data_len);
+        }
+    }
+
+    #[test]
+    fn test_count_unique_accounts() {
+        let proof_data = pubkey_validity_proof_data();
+        let context_state_authority = Pubkey::new_unique();
+
+        let instructions: Vec<Instruction> = (0..2)
+            .map(|_| {
+                let context_state_info = ContextStateInfo {
+                    context_state_account: &Pubkey::new_unique(),
+                    context_state_authority: &context_state_authority,
+                };
+                verify_pubkey_validity(Some(context_state_info), &proof_data)
+            })
+            .collect();
+
+        // two context state accounts, the shared authority, and the program id
+        assert_eq!(ProofInstruction::count_unique_accounts(&instructions), 4);
+        assert_eq!(ProofInstruction::count_unique_accounts(&[]), 0);
+    }
+
+    #[test]
+    fn test_decode_with_len() {
+        let zero_balance_data = zero_balance_proof_data();
+        let pubkey_validity_data = pubkey_validity_proof_data();
+
+        let mut buf = vec![];
+        ProofInstruction::VerifyZeroBalance.write_into(&mut buf, bytes_of(&zero_balance_data));
+        ProofInstruction::VerifyPubkeyValidity
+            .write_into(&mut buf, bytes_of(&pubkey_validity_data));
+
+        let mut decoded = vec![];
+        let mut cursor = 0;
+        while cursor < buf.len() {
+            let (instruction, len) = ProofInstruction::decode_with_len(&buf[cursor..]).unwrap();
+            decoded.push((instruction, len));
+            cursor += len;
+        }
+        assert_eq!(cursor, buf.len());
+        assert_eq!(
+            decoded,
+            vec![
+                (
+                    ProofInstruction::VerifyZeroBalance,
+                    1 + size_of::<ZeroBalanceProofData>()
+                ),
+                (
+                    ProofInstruction::VerifyPubkeyValidity,
+                    1 + size_of::<PubkeyValidityData>()
+                ),
+            ]
+        );
+
+        assert!(ProofInstruction::decode_with_len(&buf[..10]).is_none());
+    }
+
+    #[test]
+    fn test_proof_data_size() {
+        assert_eq!(
+            <ZeroBalanceProofData as ZkProofData<ZeroBalanceProofContext>>::SIZE,
+            size_of::<ZeroBalanceProofData>()
+        );
+        assert_eq!(
+            <WithdrawData as ZkProofData<WithdrawProofContext>>::SIZE,
+            size_of::<WithdrawData>()
+        );
+        assert_eq!(
+            <CiphertextCiphertextEqualityProofData as ZkProofData<
+                CiphertextCiphertextEqualityProofContext,
+            >>::SIZE,
+            size_of::<CiphertextCiphertextEqualityProofData>()
+        );
+        assert_eq!(
+            <TransferData as ZkProofData<TransferProofContext>>::SIZE,
+            size_of::<TransferData>()
+        );
+        assert_eq!(
+            <TransferWithFeeData as ZkProofData<TransferWithFeeProofContext>>::SIZE,
+            size_of::<TransferWithFeeData>()
+        );
+        assert_eq!(
+            <PubkeyValidityData as ZkProofData<PubkeyValidityProofContext>>::SIZE,
+            size_of::<PubkeyValidityData>()
+        );
+    }
+
+    #[test]
+    fn test_encode_verify_proof_from_reader() {
+        let proof_data = pubkey_validity_proof_data();
+        let proof_bytes = bytes_of(&proof_data);
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+
+        let expected = ProofInstruction::VerifyPubkeyValidity.encode_verify_proof(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account,
+                context_state_authority: &context_state_authority,
+            }),
+            &proof_data,
+        );
+        let instruction = ProofInstruction::VerifyPubkeyValidity
+            .encode_verify_proof_from_reader(
+                Some(ContextStateInfo {
+                    context_state_account: &context_state_account,
+                    context_state_authority: &context_state_authority,
+                }),
+                &mut std::io::Cursor::new(proof_bytes),
+                proof_bytes.len(),
+            )
+            .unwrap();
+        assert_eq!(instruction, expected);
+
+        // length that does not match the instruction
+        let err = ProofInstruction::VerifyPubkeyValidity
+            .encode_verify_proof_from_reader(
+                None,
+                &mut std::io::Cursor::new(proof_bytes),
+                proof_bytes.len() - 1,
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        // reader that ends before `len` bytes
+        let err = ProofInstruction::VerifyPubkeyValidity
+            .encode_verify_proof_from_reader(
+                None,
+                &mut std::io::Cursor::new(&proof_bytes[..10]),
+                proof_bytes.len(),
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        assert!(ProofInstruction::CloseContextState
+            .encode_verify_proof_from_reader(None, &mut std::io::Cursor::new(proof_bytes), 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_close_context_state_account_indices() {
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let destination_account = Pubkey::new_unique();
+
+        let instruction = close_context_state(
+            ContextStateInfo {
+                context_state_account: &context_state_account,
+                context_state_authority: &context_state_authority,
+            },
+            &destination_account,
+        );
+
+        let context_meta = &instruction.accounts[ProofInstruction::CLOSE_CONTEXT_ACCOUNT_INDEX];
+        assert_eq!(context_meta.pubkey, context_state_account);
+        assert!(context_meta.is_writable);
+
+        let destination_meta =
+            &instruction.accounts[ProofInstruction::CLOSE_DESTINATION_ACCOUNT_INDEX];
+        assert_eq!(destination_meta.pubkey, destination_account);
+        assert!(destination_meta.is_writable);
+
+        let authority_meta = &instruction.accounts[ProofInstruction::CLOSE_AUTHORITY_ACCOUNT_INDEX];
+        assert_eq!(authority_meta.pubkey, context_state_authority);
+        assert!(authority_meta.is_signer);
+    }
+
+    #[test]
+    fn test_derive_context_authority() {
+        let base = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+
+        let (authority, bump_seed) = derive_context_authority(&base, &program_id, b"transfer");
+        assert_eq!(
+            derive_context_authority(&base, &program_id, b"transfer"),
+            (authority, bump_seed)
+        );
+        assert_eq!(
+            Pubkey::create_program_address(
+                &[
+                    CONTEXT_STATE_AUTHORITY_SEED,
+                    base.as_ref(),
+                    b"transfer",
+                    &[bump_seed]
+                ],
+                &program_id,
+            )
+            .unwrap(),
+            authority
+        );
+
+        assert_ne!(
+            derive_context_authority(&base, &program_id, b"withdraw").0,
+            authority
+        );
+        assert_ne!(
+            derive_context_authority(&Pubkey::new_unique(), &program_id, b"transfer").0,
+            authority
+        );
+    }
+
+    #[test]
+    fn test_validate_distinct() {
+        let account_0 = Pubkey::new_unique();
+        let account_1 = Pubkey::new_unique();
+        let account_2 = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let other_authority = Pubkey::new_unique();
+
+        let info = |account| ContextStateInfo {
+            context_state_account: account,
+            context_state_authority: &authority,
+        };
+
+        assert!(info(&account_0).conflicts_with(&ContextStateInfo {
+            context_state_account: &account_0,
+            context_state_authority: &other_authority,
+        }));
+        assert!(!info(&account_0).conflicts_with(&info(&account_1)));
+
+        assert_eq!(ContextStateInfo::validate_distinct(&[]), Ok(()));
+        assert_eq!(
+            ContextStateInfo::validate_distinct(&[
+                info(&account_0),
+                info(&account_1),
+                info(&account_2)
+            ]),
+            Ok(())
+        );
+        assert_eq!(
+            ContextStateInfo::validate_distinct(&[
+                info(&account_0),
+                info(&account_1),
+                info(&account_0),
+                info(&account_1),
+            ]),
+            Err(2)
+        );
+    }
+
+    #[test]
+    fn test_close_context_states() {
+        let context_state_accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let context_state_authority = Pubkey::new_unique();
+        let destination_account = Pubkey::new_unique();
+
+        let context_state_infos: Vec<_> = context_state_accounts
+            .iter()
+            .map(|context_state_account| ContextStateInfo {
+                context_state_account,
+                context_state_authority: &context_state_authority,
+            })
+            .collect();
+
+        let instructions = close_context_states(&context_state_infos, &destination_account);
+        assert_eq!(instructions.len(), context_state_infos.len());
+        for (instruction, context_state_info) in instructions.iter().zip(context_state_infos) {
+            assert_eq!(
+                instruction,
+                &close_context_state(context_state_info, &destination_account)
+            );
+            assert_eq!(
+                ProofInstruction::instruction_type(&instruction.data),
+                Some(ProofInstruction::CloseContextState)
+            );
+            assert_eq!(
+                instruction.accounts[ProofInstruction::CLOSE_CONTEXT_ACCOUNT_INDEX].pubkey,
+                *context_state_info.context_state_account
+            );
+            assert_eq!(
+                instruction.accounts[ProofInstruction::CLOSE_DESTINATION_ACCOUNT_INDEX].pubkey,
+                destination_account
+            );
+        }
+
+        assert!(close_context_states(&[], &destination_account).is_empty());
+    }
+
+    #[test]
+    fn test_check_context() {
+        let proof_data = zero_balance_proof_data();
+        let instruction =
+            ProofInstruction::VerifyZeroBalance.encode_verify_proof(None, &proof_data);
+
+        assert!(ProofInstruction::check_context::<
+            ZeroBalanceProofData,
+            ZeroBalanceProofContext,
+        >(&instruction.data, &proof_data.context));
+
+        let other_proof_data = zero_balance_proof_data();
+        assert!(!ProofInstruction::check_context::<
+            ZeroBalanceProofData,
+            ZeroBalanceProofContext,
+        >(&instruction.data, &other_proof_data.context));
+
+        assert!(!ProofInstruction::check_context::<
+            ZeroBalanceProofData,
+            ZeroBalanceProofContext,
+        >(&instruction.data[..10], &proof_data.context));
+    }
+
+    #[test]
+    fn test_encode_verify_proof_with_extra_accounts() {
+        let proof_data = pubkey_validity_proof_data();
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let extra_accounts = vec![
+            AccountMeta::new(Pubkey::new_unique(), false),
+            AccountMeta::new_readonly(Pubkey::new_unique(), true),
+        ];
+
+        let instruction = ProofInstruction::VerifyPubkeyValidity
+            .encode_verify_proof_with_extra_accounts(
+                Some(ContextStateInfo {
+                    context_state_account: &context_state_account,
+                    context_state_authority: &context_state_authority,
+                }),
+                &proof_data,
+                &extra_accounts,
+            );
+        assert_eq!(instruction.accounts.len(), 4);
+        assert_eq!(instruction.accounts[0].pubkey, context_state_account);
+        assert_eq!(instruction.accounts[1].pubkey, context_state_authority);
+        assert_eq!(&instruction.accounts[2..], &extra_accounts[..]);
+        assert_eq!(
+            instruction.data,
+            ProofInstruction::VerifyPubkeyValidity
+                .encode_verify_proof(None, &proof_data)
+                .data
+        );
+    }
+
+    #[test]
+    fn test_proof_type_id() {
+        assert_eq!(ProofInstruction::CloseContextState.proof_type_id(), None);
+        assert_eq!(
+            ProofInstruction::VerifyZeroBalance.proof_type_id(),
+            Some(TypeId::of::<ZeroBalanceProofData>())
+        );
+        assert_eq!(
+            ProofInstruction::VerifyWithdraw.proof_type_id(),
+            Some(TypeId::of::<WithdrawData>())
+        );
+        assert_eq!(
+            ProofInstruction::VerifyCiphertextCiphertextEquality.proof_type_id(),
+            Some(TypeId::of::<CiphertextCiphertextEqualityProofData>())
+        );
+        assert_eq!(
+            ProofInstruction::VerifyTransfer.proof_type_id(),
+            Some(TypeId::of::<TransferData>())
+        );
+        assert_eq!(
+            ProofInstruction::VerifyTransferWithFee.proof_type_id(),
+            Some(TypeId::of::<TransferWithFeeData>())
+        );
+        assert_eq!(
+            ProofInstruction::VerifyPubkeyValidity.proof_type_id(),
+            Some(TypeId::of::<PubkeyValidityData>())
+        );
+    }
+
+    #[test]
+    fn test_proof_packet() {
+        let proof_data = pubkey_validity_proof_data();
+        let proof_bytes = bytes_of(&proof_data);
+
+        let packet = ProofInstruction::VerifyPubkeyValidity.to_packet(proof_bytes);
+        assert_eq!(packet.len(), 6 + proof_bytes.len());
+        assert_eq!(
+            ProofInstruction::from_packet(&packet),
+            Some((
+                PROOF_PACKET_VERSION,
+                ProofInstruction::VerifyPubkeyValidity,
+                proof_bytes.to_vec()
+            ))
+        );
+
+        // unknown version
+        let mut unknown_version = packet.clone();
+        unknown_version[0] = PROOF_PACKET_VERSION + 1;
+        assert_eq!(ProofInstruction::from_packet(&unknown_version), None);
+
+        // truncated proof bytes
+        assert_eq!(
+            ProofInstruction::from_packet(&packet[..packet.len() - 1]),
+            None
+        );
+        assert_eq!(ProofInstruction::from_packet(&packet[..4]), None);
+    }
+
+    #[test]
+    fn test_encode_verify_proof_checked() {
+        let zeroed_proof_data = PubkeyValidityData::zeroed();
+        assert_eq!(
+            ProofInstruction::VerifyPubkeyValidity
+                .encode_verify_proof_checked(None, &zeroed_proof_data),
+            Err(ProofDataError::AllZeroBytes)
+        );
+
+        let proof_data = pubkey_validity_proof_data();
+        assert_eq!(
+            ProofInstruction::VerifyPubkeyValidity.encode_verify_proof_checked(None, &proof_data),
+            Ok(ProofInstruction::VerifyPubkeyValidity.encode_verify_proof(None, &proof_data))
+        );
+    }
+
+    #[test]
+    fn test_validate_all() {
+        let proof_data = pubkey_validity_proof_data();
+        let verify_instruction = verify_pubkey_validity(None, &proof_data);
+
+        let mut bad_discriminant_instruction = verify_instruction.clone();
+        bad_discriminant_instruction.data[0] = u8::MAX;
+
+        let mut empty_instruction = verify_instruction.clone();
+        empty_instruction.data.clear();
+
+        let mut foreign_instruction = bad_discriminant_instruction.clone();
+        foreign_instruction.program_id = Pubkey::new_unique();
+
+        assert_eq!(
+            ProofInstruction::validate_all(&[
+                verify_instruction.clone(),
+                foreign_instruction.clone()
+            ]),
+            Ok(())
+        );
+        assert_eq!(
+            ProofInstruction::validate_all(&[
+                verify_instruction,
+                bad_discriminant_instruction,
+                foreign_instruction,
+                empty_instruction,
+            ]),
+            Err(vec![1, 3])
+        );
+    }
+
+    #[test]
+    fn test_signer_accounts() {
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let context_state_info = ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority: &context_state_authority,
+        };
+
+        let proof_data = pubkey_validity_proof_data();
+        let instruction = verify_pubkey_validity(Some(context_state_info), &proof_data);
+        assert!(ProofInstruction::signer_accounts(&instruction.accounts).is_empty());
+
+        let instruction = close_context_state(context_state_info, &Pubkey::new_unique());
+        assert_eq!(
+            ProofInstruction::signer_accounts(&instruction.accounts),
+            vec![context_state_authority]
+        );
+    }
+
+    #[test]
+    fn test_proof_kind() {
+        fn describe<T: ProofKind>() -> (u8, usize, &'static str) {
+            (
+                ToPrimitive::to_u8(&T::INSTRUCTION).unwrap(),
+                T::SIZE,
+                T::NAME,
+            )
+        }
+
+        assert_eq!(
+            describe::<TransferData>(),
+            (4, size_of::<TransferData>(), "TransferData")
+        );
+        assert_eq!(
+            describe::<PubkeyValidityData>(),
+            (6, size_of::<PubkeyValidityData>(), "PubkeyValidityData")
+        );
+        assert_eq!(
+            PubkeyValidityData::INSTRUCTION.proof_data_len(),
+            PubkeyValidityData::SIZE
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "zstd")]
+    fn test_encode_compressed() {
+        let proof_data = pubkey_validity_proof_data();
+        let proof_bytes = bytes_of(&proof_data);
+
+        let compressed = ProofInstruction::VerifyPubkeyValidity.encode_compressed(proof_bytes);
+        assert_eq!(
+            ProofInstruction::decode_compressed(&compressed),
+            Some((ProofInstruction::VerifyPubkeyValidity, proof_bytes.to_vec()))
+        );
+
+        // uncompressed instruction data is rejected
+        let instruction = verify_pubkey_validity(None, &proof_data);
+        assert_eq!(ProofInstruction::decode_compressed(&instruction.data), None);
+
+        let repetitive_bytes = [7_u8; 1024];
+        let compressed = ProofInstruction::VerifyTransfer.encode_compressed(&repetitive_bytes);
+        assert!(compressed.len() < repetitive_bytes.len());
+        assert_eq!(
+            ProofInstruction::decode_compressed(&compressed),
+            Some((ProofInstruction::VerifyTransfer, repetitive_bytes.to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_into_message() {
+        let payer = Pubkey::new_unique();
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let proof_data = pubkey_validity_proof_data();
+
+        let instructions = vec![
+            verify_pubkey_validity(
+                Some(ContextStateInfo {
+                    context_state_account: &context_state_account,
+                    context_state_authority: &context_state_authority,
+                }),
+                &proof_data,
+            ),
+            verify_pubkey_validity(None, &proof_data),
+        ];
+        let lookup_table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![context_state_authority, crate::zk_token_proof_program::id()],
+        };
+
+        let message = ProofInstruction::into_message(
+            instructions.clone(),
+            &payer,
+            &[lookup_table.clone()],
+            Hash::default(),
+        )
+        .unwrap();
+
+        assert_eq!(message.instructions.len(), instructions.len());
+        for (compiled_instruction, instruction) in message.instructions.iter().zip(&instructions) {
+            assert_eq!(compiled_instruction.data, instruction.data);
+        }
+
+        assert_eq!(message.account_keys[0], payer);
+        assert!(message
+            .account_keys
+            .contains(&crate::zk_token_proof_program::id()));
+        assert!(!message.account_keys.contains(&context_state_authority));
+        assert_eq!(message.address_table_lookups.len(), 1);
+        assert_eq!(
+            message.address_table_lookups[0].account_key,
+            lookup_table.key
+        );
+        assert_eq!(message.address_table_lookups[0].readonly_indexes, vec![0]);
+    }
+
+    #[test]
+    fn test_reclaimable_rent() {
+        let context_state_accounts = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let context_state_authority = Pubkey::new_unique();
+        let info = |context_state_account| ContextStateInfo {
+            context_state_account,
+            context_state_authority: &context_state_authority,
+        };
+        let rent = Rent::default();
+
+        let context_states = [
+            (
+                info(&context_state_accounts[0]),
+                ProofInstruction::VerifyPubkeyValidity,
+            ),
+            (
+                info(&context_state_accounts[1]),
+                ProofInstruction::VerifyTransfer,
+            ),
+        ];
+        assert_eq!(
+            ProofInstruction::reclaimable_rent(&context_states, &rent),
+            rent.minimum_balance(33 + size_of::<PubkeyValidityProofContext>())
+                + rent.minimum_balance(33 + size_of::<TransferProofContext>())
+        );
+
+        assert_eq!(ProofInstruction::reclaimable_rent(&[], &rent), 0);
+    }
+
+    #[test]
+    fn test_context_data_offset() {
+        assert_eq!(
+            ProofInstruction::CloseContextState.context_data_offset(),
+            None
+        );
+
+        // 32 bytes for the authority and 1 byte for the proof type
+        assert_eq!(
+            ProofInstruction::VerifyZeroBalance.context_data_offset(),
+            Some(33)
+        );
+
+        let proof_data = zero_balance_proof_data();
+        let context_state = ProofContextState::encode(
+            &Pubkey::new_unique(),
+            ProofType::ZeroBalance,
+            proof_data.context_data(),
+        );
+        let offset = ProofInstruction::VerifyZeroBalance
+            .context_data_offset()
+            .unwrap();
+        assert_eq!(
+            &context_state[offset..],
+            bytes_of(proof_data.context_data())
+        );
+    }
+
+    #[test]
+    fn test_encode_verify_proof_with_discriminant() {
+        let proof_data = pubkey_validity_proof_data();
+        let instruction = ProofInstruction::VerifyPubkeyValidity
+            .encode_verify_proof_with_discriminant(42, None, &proof_data);
+
+        assert_eq!(instruction.data[0], 42);
+        assert_eq!(&instruction.data[1..], bytes_of(&proof_data));
+    }
+
+    #[test]
+    fn test_close_context_states_same_authority() {
+        let context_state_accounts = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let context_state_authority = Pubkey::new_unique();
+        let destination_account = Pubkey::new_unique();
+
+        let instructions = close_context_states_same_authority(
+            &context_state_accounts.iter().collect::<Vec<_>>(),
+            &context_state_authority,
+            &destination_account,
+        )
+        .unwrap();
+        assert_eq!(instructions.len(), 3);
+        for (instruction, context_state_account) in instructions.iter().zip(&context_state_accounts)
+        {
+            let accounts = &instruction.accounts;
+            assert_eq!(
+                accounts[ProofInstruction::CLOSE_CONTEXT_ACCOUNT_INDEX].pubkey,
+                *context_state_account
+            );
+            assert_eq!(
+                accounts[ProofInstruction::CLOSE_DESTINATION_ACCOUNT_INDEX].pubkey,
+                destination_account
+            );
+            assert_eq!(
+                accounts[ProofInstruction::CLOSE_AUTHORITY_ACCOUNT_INDEX].pubkey,
+                context_state_authority
+            );
+        }
+
+        assert_eq!(
+            close_context_states_same_authority(
+                &[
+                    &context_state_accounts[0],
+                    &context_state_accounts[1],
+                    &context_state_accounts[1],
+                ],
+                &context_state_authority,
+                &destination_account,
+            ),
+            Err(2)
+        );
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let proof_data = pubkey_validity_proof_data();
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let context_state_info = ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority: &context_state_authority,
+        };
+
+        let instruction = verify_pubkey_validity(Some(context_state_info), &proof_data);
+        assert_eq!(ProofInstruction::dry_run(&instruction), Ok(()));
+        let instruction = close_context_state(context_state_info, &Pubkey::new_unique());
+        assert_eq!(ProofInstruction::dry_run(&instruction), Ok(()));
+
+        let mut malformed_instruction =
+            verify_pubkey_validity(Some(context_state_info), &proof_data);
+        let program_id = Pubkey::new_unique();
+        malformed_instruction.program_id = program_id;
+        malformed_instruction.data.pop();
+        malformed_instruction.accounts.pop();
+        assert_eq!(
+            ProofInstruction::dry_run(&malformed_instruction),
+            Err(vec![
+                DryRunIssue::ProgramId(ProgramIdError {
+                    found: program_id,
+                    expected: crate::zk_token_proof_program::id(),
+                }),
+                DryRunIssue::InvalidDataLength {
+                    expected: size_of::<PubkeyValidityData>(),
+                    found: size_of::<PubkeyValidityData>() - 1,
+                },
+                DryRunIssue::InvalidAccounts,
+            ])
+        );
+
+        malformed_instruction.data[0] = u8::MAX;
+        assert_eq!(
+            ProofInstruction::dry_run(&malformed_instruction),
+            Err(vec![
+                DryRunIssue::ProgramId(ProgramIdError {
+                    found: program_id,
+                    expected: crate::zk_token_proof_program::id(),
+                }),
+                DryRunIssue::InvalidDiscriminant,
+            ])
+        );
+    }
+
+    #[test]
+    fn test_describe_all() {
+        let descriptions = ProofInstruction::describe_all();
+        assert_eq!(descriptions.len(), 7);
+
+        for (discriminant, description) in descriptions.iter().enumerate() {
+            assert_eq!(description.discriminant, discriminant as u8);
+            assert_eq!(
+                ProofInstruction::instruction_type(&[description.discriminant]),
+                Some(description.instruction)
+            );
+            assert!(!description.description.is_empty());
+        }
+
+        assert_eq!(
+            descriptions[0].instruction,
+            ProofInstruction::CloseContextState
+        );
+        assert_eq!(descriptions[0].proof_data_name, None);
+        assert_eq!(
+            descriptions[4].proof_data_name,
+            Some(<TransferData as ProofKind>::NAME)
+        );
+    }
+
+    #[test]
+    fn test_redact_for_log() {
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let proof_data = pubkey_validity_proof_data();
+        let instruction = verify_pubkey_validity(
+            Some(ContextStateInfo {
+                context_state_account: &context_state_account,
+                context_state_authority: &context_state_authority,
+            }),
+            &proof_data,
+        );
+
+        let redacted_instruction = ProofInstruction::redact_for_log(&instruction);
+        assert_eq!(redacted_instruction.program_id, instruction.program_id);
+        assert_eq!(redacted_instruction.accounts, instruction.accounts);
+        assert_eq!(redacted_instruction.data.len(), instruction.data.len());
+        assert_eq!(redacted_instruction.data[0], instruction.data[0]);
+        assert!(redacted_instruction.data[1..].iter().all(|byte| *byte == 0));
+
+        let mut empty_instruction = instruction;
+        empty_instruction.data.clear();
+        assert!(ProofInstruction::redact_for_log(&empty_instruction)
+            .data
+            .is_empty());
+    }
+
+    #[test]
+    fn test_context_state_info_validate() {
+        let context_state_account = Pubkey::new_unique();
+        let context_state_authority = Pubkey::new_unique();
+        let default_pubkey = Pubkey::default();
+
+        assert_eq!(
+            ContextStateInfo {
+                context_state_account: &context_state_account,
+                context_state_authority: &context_state_authority,
+            }
+            .validate(),
+            Ok(())
+        );
+        assert_eq!(
+            ContextStateInfo {
+                context_state_account: &context_state_account,
+                context_state_authority: &context_state_account,
+            }
+            .validate(),
+            Err(ContextStateError::AccountIsAuthority)
+        );
+        assert_eq!(
+            ContextStateInfo {
+                context_state_account: &context_state_account,
+                context_state_authority: &default_pubkey,
+            }
+            .validate(),
+            Err(ContextStateError::DefaultAuthority)
+        );
+        assert_eq!(
+            ContextStateInfo {
+                context_state_account: &default_pubkey,
+                context_state_authority: &context_state_authority,
+            }
+            .validate(),
+            Err(ContextStateError::DefaultAccount)
+        );
+    }
+
+    #[test]
+    fn test_proof_instruction_category() {
+        for instruction in [
+            ProofInstruction::VerifyZeroBalance,
+            ProofInstruction::VerifyWithdraw,
+            ProofInstruction::VerifyCiphertextCiphertextEquality,
+            ProofInstruction::VerifyTransfer,
+            ProofInstruction::VerifyTransferWithFee,
+            ProofInstruction::VerifyPubkeyValidity,
+        ] {
+            assert_eq!(instruction.category(), ProofCategory::Verify);
+            assert!(instruction.is_verify());
+            assert!(!instruction.is_close());
+        }
+
+        let instruction = ProofInstruction::CloseContextState;
+        assert_eq!(instruction.category(), ProofCategory::Close);
+        assert!(instruction.is_close());
+        assert!(!instruction.is_verify());
+    }
+
+    #[test]
+    fn test_proof_instruction_within_budget() {
+        let budget_bytes = 256;
+
+        assert!(!ProofInstruction::VerifyTransferWithFee.within_budget(budget_bytes));
+        assert!(ProofInstruction::VerifyPubkeyValidity.within_budget(budget_bytes));
+        assert!(ProofInstruction::CloseContextState.within_budget(1));
+        assert!(!ProofInstruction::CloseContextState.within_budget(0));
+
+        let encoded_size = ProofInstruction::VerifyWithdraw.encoded_size();
+        assert_eq!(encoded_size, 1 + WithdrawData::SIZE);
+        assert!(ProofInstruction::VerifyWithdraw.within_budget(encoded_size));
+        assert!(!ProofInstruction::VerifyWithdraw.within_budget(encoded_size - 1));
+    }
+
+    #[test]
+    fn test_context_account() {
+        let proof_data = pubkey_validity_proof_data();
+        let context_state_account = Pubkey::new_unique();
+        let context_state_info = ContextStateInfo {
+            context_state_account: &context_state_account,
+            context_state_authority: &Pubkey::new_unique(),
+        };
+
+        let instruction = verify_pubkey_validity(Some(context_state_info), &proof_data);
+        assert_eq!(
+            ProofInstruction::context_account(&instruction),
+            Some(&context_state_account)
+        );
+
+        let instruction = verify_pubkey_validity(None, &proof_data);
+        assert_eq!(ProofInstruction::context_account(&instruction), None);
+
+        let instruction = close_context_state(context_state_info, &Pubkey::new_unique());
+        assert_eq!(ProofInstruction::context_account(&instruction), None);
     }
 }
@@ -1,2 +1,44 @@
 // Program Id of the ZkToken Proof program
 solana_program::declare_id!("ZkTokenProof1111111111111111111111111111111");
+
+/// The program id of the ZkToken Proof program as a constant.
+///
+/// This is an alias of `ID` that can be used in `const` contexts, e.g. to validate the target
+/// program of a cross-program invocation together with `check_id`.
+pub const PROGRAM_ID: solana_program::pubkey::Pubkey = ID;
+
+/// The number of compute units that the ZkToken Proof program consumes to process a
+/// `VerifyZeroBalance` instruction.
+pub const VERIFY_ZERO_BALANCE_COMPUTE_UNITS: u64 = 6_012;
+
+/// The number of compute units that the ZkToken Proof program consumes to process a
+/// `VerifyWithdraw` instruction.
+pub const VERIFY_WITHDRAW_COMPUTE_UNITS: u64 = 112_454;
+
+/// The number of compute units that the ZkToken Proof program consumes to process a
+/// `VerifyCiphertextCiphertextEquality` instruction.
+pub const VERIFY_CIPHERTEXT_CIPHERTEXT_EQUALITY_COMPUTE_UNITS: u64 = 7_943;
+
+/// The number of compute units that the ZkToken Proof program consumes to process a
+/// `VerifyTransfer` instruction.
+pub const VERIFY_TRANSFER_COMPUTE_UNITS: u64 = 219_290;
+
+/// The number of compute units that the ZkToken Proof program consumes to process a
+/// `VerifyTransferWithFee` instruction.
+pub const VERIFY_TRANSFER_WITH_FEE_COMPUTE_UNITS: u64 = 407_121;
+
+/// The number of compute units that the ZkToken Proof program consumes to process a
+/// `VerifyPubkeyValidity` instruction.
+pub const VERIFY_PUBKEY_VALIDITY_COMPUTE_UNITS: u64 = 2_619;
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_program::pubkey::Pubkey};
+
+    #[test]
+    fn test_check_id() {
+        assert!(check_id(&id()));
+        assert!(check_id(&PROGRAM_ID));
+        assert!(!check_id(&Pubkey::new_unique()));
+    }
+}